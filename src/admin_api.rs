@@ -0,0 +1,593 @@
+use worker::*;
+use serde::Deserialize;
+use chrono::Datelike;
+
+use crate::backup::{backup_table, to_csv, Backup, ConflictMode, BACKUP_TABLES};
+use crate::calendar::{CalendarKind, IcsCalendar};
+use crate::database::{DatabaseClient, TaskStatus, TaskUpdate};
+use crate::holiday_calendar::CalendarImport;
+use crate::leave::{leave_from_ics, LeaveRecord};
+use crate::project_codes::{ProjectCodeRule, ProjectCodes};
+use crate::team::{TeamMember, TOKEN_OWNER};
+
+/// 校验管理接口的 Authorization: Bearer <ADMIN_TOKEN>，未配置 ADMIN_TOKEN 时拒绝所有请求
+pub fn is_authorized(req: &Request, env: &Env) -> bool {
+    let admin_token = match env.var("ADMIN_TOKEN") {
+        Ok(token) => token.to_string(),
+        Err(_) => return false,
+    };
+    if admin_token.is_empty() {
+        return false;
+    }
+
+    match req.headers().get("Authorization") {
+        Ok(Some(header)) => header.strip_prefix("Bearer ").map(|token| token.trim() == admin_token).unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// 获取查询参数
+pub fn query_param(url: &Url, name: &str) -> Option<String> {
+    url.query_pairs()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.to_string())
+}
+
+/// 将处理结果转换为 JSON 响应，失败时返回 500
+fn json_result(result: anyhow::Result<serde_json::Value>) -> Result<Response> {
+    match result {
+        Ok(response) => Response::from_json(&response),
+        Err(e) => {
+            let response = serde_json::json!({
+                "success": false,
+                "error": e.to_string()
+            });
+            Ok(Response::from_json(&response)?.with_status(500))
+        }
+    }
+}
+
+/// 查看（GET ?year=）或上传（POST）一整年的节假日日历
+pub async fn handle_holiday_calendar(req: &mut Request, env: &Env, url: &Url) -> Result<Response> {
+    if !is_authorized(req, env) {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let db = env.d1("DB")?;
+    let db_client = DatabaseClient::new(&db);
+    db_client.migrate().await
+        .map_err(|e| Error::RustError(format!("执行数据库迁移失败: {}", e)))?;
+
+    let result = match req.method() {
+        Method::Post => {
+            let import: CalendarImport = match req.json().await {
+                Ok(import) => import,
+                Err(e) => return Response::error(format!("日历数据格式无效: {}", e), 400),
+            };
+            let year = import.year;
+            let entries = match import.into_entries() {
+                Ok(entries) => entries,
+                Err(e) => return Response::error(e.to_string(), 400),
+            };
+            db_client.import_holiday_year(year, &entries, "import").await
+                .map(|count| serde_json::json!({
+                    "success": true,
+                    "year": year,
+                    "imported": count
+                }))
+        }
+        Method::Get => {
+            let year = query_param(url, "year")
+                .and_then(|value| value.parse::<i32>().ok())
+                .unwrap_or_else(|| chrono::Utc::now().year());
+            db_client.list_holiday_year(year).await
+                .map(|entries| serde_json::json!({
+                    "success": true,
+                    "year": year,
+                    "days": entries
+                }))
+        }
+        _ => return Response::error("Method not allowed", 405),
+    };
+
+    json_result(result)
+}
+
+/// 查看（GET）、新增或更新（POST）、删除（DELETE ?login=）团队成员
+pub async fn handle_team_members(req: &mut Request, env: &Env, url: &Url) -> Result<Response> {
+    if !is_authorized(req, env) {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let db = env.d1("DB")?;
+    let db_client = DatabaseClient::new(&db);
+    db_client.migrate().await
+        .map_err(|e| Error::RustError(format!("执行数据库迁移失败: {}", e)))?;
+
+    let result = match req.method() {
+        Method::Get => {
+            db_client.list_team_members().await
+                .map(|members| serde_json::json!({
+                    "success": true,
+                    "members": members
+                }))
+        }
+        Method::Post => {
+            let member: TeamMember = match req.json().await {
+                Ok(member) => member,
+                Err(e) => return Response::error(format!("成员数据格式无效: {}", e), 400),
+            };
+            if member.login.trim().is_empty() || member.is_token_owner() {
+                return Response::error("login 不能为空", 400);
+            }
+            let kind = match member.calendar_kind() {
+                Ok(kind) => kind,
+                Err(e) => return Response::error(e.to_string(), 400),
+            };
+            if let Err(e) = member.tz().and_then(|_| member.local_send_time()) {
+                return Response::error(e.to_string(), 400);
+            }
+            if let CalendarKind::Ics(name) = &kind {
+                match db_client.get_ics_calendar(name).await {
+                    Ok(Some(_)) => {}
+                    Ok(None) => return Response::error(format!("ICS 日历 {} 不存在，请先上传", name), 400),
+                    Err(e) => return json_result(Err(e)),
+                }
+            }
+            db_client.upsert_team_member(&member).await
+                .map(|_| serde_json::json!({
+                    "success": true,
+                    "member": member
+                }))
+        }
+        Method::Delete => {
+            let login = match query_param(url, "login") {
+                Some(login) => login,
+                None => return Response::error("缺少 login 参数", 400),
+            };
+            db_client.delete_team_member(&login).await
+                .map(|_| serde_json::json!({
+                    "success": true,
+                    "login": login
+                }))
+        }
+        _ => return Response::error("Method not allowed", 405),
+    };
+
+    json_result(result)
+}
+
+/// 查看（GET）或上传（POST ?name=，请求体为 ICS 文本）ICS 日历
+pub async fn handle_ics_calendars(req: &mut Request, env: &Env, url: &Url) -> Result<Response> {
+    if !is_authorized(req, env) {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let db = env.d1("DB")?;
+    let db_client = DatabaseClient::new(&db);
+    db_client.migrate().await
+        .map_err(|e| Error::RustError(format!("执行数据库迁移失败: {}", e)))?;
+
+    let result = match req.method() {
+        Method::Get => {
+            db_client.list_ics_calendars().await
+                .map(|names| serde_json::json!({
+                    "success": true,
+                    "calendars": names
+                }))
+        }
+        Method::Post => {
+            let name = match query_param(url, "name") {
+                Some(name) if !name.trim().is_empty() => name.trim().to_string(),
+                _ => return Response::error("缺少 name 参数", 400),
+            };
+            let content = req.text().await?;
+            if let Err(e) = IcsCalendar::parse(&content) {
+                return Response::error(format!("ICS 文件格式无效: {}", e), 400);
+            }
+            db_client.save_ics_calendar(&name, &content).await
+                .map(|_| serde_json::json!({
+                    "success": true,
+                    "name": name,
+                    "calendar": format!("ics:{}", name)
+                }))
+        }
+        _ => return Response::error("Method not allowed", 405),
+    };
+
+    json_result(result)
+}
+
+/// 查看（GET ?user=）、登记（POST）、删除（DELETE ?id=）请假记录
+pub async fn handle_leave(req: &mut Request, env: &Env, url: &Url) -> Result<Response> {
+    if !is_authorized(req, env) {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let db = env.d1("DB")?;
+    let db_client = DatabaseClient::new(&db);
+    db_client.migrate().await
+        .map_err(|e| Error::RustError(format!("执行数据库迁移失败: {}", e)))?;
+
+    let result = match req.method() {
+        Method::Get => {
+            let login = query_param(url, "user");
+            db_client.list_leave(login.as_deref()).await
+                .map(|leaves| serde_json::json!({
+                    "success": true,
+                    "leave": leaves
+                }))
+        }
+        Method::Post => {
+            let mut leave: LeaveRecord = match req.json().await {
+                Ok(leave) => leave,
+                Err(e) => return Response::error(format!("请假数据格式无效: {}", e), 400),
+            };
+            if let Err(e) = leave.date_range() {
+                return Response::error(e.to_string(), 400);
+            }
+            leave.id = None;
+            leave.source = "manual".to_string();
+            db_client.add_leave(&leave).await
+                .map(|_| serde_json::json!({
+                    "success": true,
+                    "leave": leave
+                }))
+        }
+        Method::Delete => {
+            let id = match query_param(url, "id").and_then(|value| value.parse::<i64>().ok()) {
+                Some(id) => id,
+                None => return Response::error("缺少 id 参数", 400),
+            };
+            db_client.delete_leave(id).await
+                .map(|_| serde_json::json!({
+                    "success": true,
+                    "id": id
+                }))
+        }
+        _ => return Response::error("Method not allowed", 405),
+    };
+
+    json_result(result)
+}
+
+/// 导入成员的 ICS 请假日历（POST ?user=，请求体为 ICS 文本，例如飞书日历导出的文件）
+/// 默认只导入标题含请假关键词的日程，?all=true 时导入全部日程；重复导入会替换上次导入的记录
+pub async fn handle_leave_ics(req: &mut Request, env: &Env, url: &Url) -> Result<Response> {
+    if !is_authorized(req, env) {
+        return Response::error("Unauthorized", 401);
+    }
+    if req.method() != Method::Post {
+        return Response::error("Method not allowed", 405);
+    }
+
+    let login = match query_param(url, "user") {
+        Some(login) if !login.trim().is_empty() && login.trim() != TOKEN_OWNER => login.trim().to_string(),
+        _ => return Response::error("缺少 user 参数，请填写 GitHub 用户名", 400),
+    };
+    let all_events = query_param(url, "all").map(|value| value == "true").unwrap_or(false);
+
+    let content = req.text().await?;
+    let leaves = match leave_from_ics(&login, &content, all_events) {
+        Ok(leaves) => leaves,
+        Err(e) => return Response::error(format!("ICS 文件格式无效: {}", e), 400),
+    };
+
+    let db = env.d1("DB")?;
+    let db_client = DatabaseClient::new(&db);
+    db_client.migrate().await
+        .map_err(|e| Error::RustError(format!("执行数据库迁移失败: {}", e)))?;
+
+    let result = db_client.replace_ics_leave(&login, &leaves).await
+        .map(|count| serde_json::json!({
+            "success": true,
+            "user": login,
+            "imported": count,
+            "leave": leaves
+        }));
+
+    json_result(result)
+}
+
+/// 查询站会报告历史（GET ?user=&from=&to=），日期格式 YYYY-MM-DD，默认最近 7 天
+pub async fn handle_reports(req: &Request, env: &Env, url: &Url) -> Result<Response> {
+    if !is_authorized(req, env) {
+        return Response::error("Unauthorized", 401);
+    }
+    if req.method() != Method::Get {
+        return Response::error("Method not allowed", 405);
+    }
+
+    let parse_date = |name: &str| -> std::result::Result<Option<chrono::NaiveDate>, String> {
+        match query_param(url, name) {
+            Some(value) => chrono::NaiveDate::parse_from_str(&value, "%Y-%m-%d")
+                .map(Some)
+                .map_err(|_| format!("{} 参数格式无效，请使用 YYYY-MM-DD 格式", name)),
+            None => Ok(None),
+        }
+    };
+    let (from, to) = match (parse_date("from"), parse_date("to")) {
+        (Ok(from), Ok(to)) => {
+            let to = to.unwrap_or_else(|| chrono::Utc::now().date_naive());
+            (from.unwrap_or(to - chrono::Duration::days(6)), to)
+        }
+        (Err(e), _) | (_, Err(e)) => return Response::error(e, 400),
+    };
+    if to < from {
+        return Response::error("to 不能早于 from", 400);
+    }
+
+    let db = env.d1("DB")?;
+    let db_client = DatabaseClient::new(&db);
+    db_client.migrate().await
+        .map_err(|e| Error::RustError(format!("执行数据库迁移失败: {}", e)))?;
+
+    let login = query_param(url, "user");
+    let from = from.format("%Y-%m-%d").to_string();
+    let to = to.format("%Y-%m-%d").to_string();
+    let result = db_client.list_standup_reports(login.as_deref(), &from, &to).await
+        .map(|reports| serde_json::json!({
+            "success": true,
+            "from": from,
+            "to": to,
+            "reports": reports.iter().map(|report| report.to_json()).collect::<Vec<_>>()
+        }));
+
+    json_result(result)
+}
+
+/// 查看（GET ?q=&status=&limit=，附带关联的 PR）、调整（PATCH）、删除（DELETE ?key=）Taiga 任务
+/// PATCH 请求体：{"task_key": "...", "first_seen_date": "YYYY-MM-DD", "total_days": N, "status": "open|done"}，字段均可省略
+pub async fn handle_tasks(req: &mut Request, env: &Env, url: &Url) -> Result<Response> {
+    if !is_authorized(req, env) {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let db = env.d1("DB")?;
+    let db_client = DatabaseClient::new(&db);
+    db_client.migrate().await
+        .map_err(|e| Error::RustError(format!("执行数据库迁移失败: {}", e)))?;
+
+    let result = match req.method() {
+        Method::Get => {
+            let status = match query_param(url, "status") {
+                Some(value) => match TaskStatus::parse(&value) {
+                    Some(status) => Some(status),
+                    None => return Response::error("status 参数无效，可选 open 或 done", 400),
+                },
+                None => None,
+            };
+            let limit = query_param(url, "limit")
+                .and_then(|value| value.parse::<i32>().ok())
+                .unwrap_or(100)
+                .clamp(1, 1000);
+            let query = query_param(url, "q");
+            db_client.list_task_history(query.as_deref(), status, limit).await
+                .map(|tasks| serde_json::json!({
+                    "success": true,
+                    "tasks": tasks
+                }))
+        }
+        Method::Patch => {
+            let update: TaskUpdate = match req.json().await {
+                Ok(update) => update,
+                Err(e) => return Response::error(format!("任务数据格式无效: {}", e), 400),
+            };
+            if let Err(e) = update.validate() {
+                return Response::error(e.to_string(), 400);
+            }
+            db_client.update_task_record(&update, crate::task_days_mode(env)).await
+                .map(|task| serde_json::json!({
+                    "success": true,
+                    "task": task
+                }))
+        }
+        Method::Delete => {
+            let task_key = match query_param(url, "key") {
+                Some(key) if !key.trim().is_empty() => key,
+                _ => return Response::error("缺少 key 参数", 400),
+            };
+            db_client.delete_task_record(&task_key).await
+                .map(|_| serde_json::json!({
+                    "success": true,
+                    "task_key": task_key
+                }))
+        }
+        _ => return Response::error("Method not allowed", 405),
+    };
+
+    json_result(result)
+}
+
+/// 合并重复的任务（POST {"from": "...", "into": "..."}），from 的记录并入 into 后删除
+pub async fn handle_task_merge(req: &mut Request, env: &Env) -> Result<Response> {
+    if !is_authorized(req, env) {
+        return Response::error("Unauthorized", 401);
+    }
+    if req.method() != Method::Post {
+        return Response::error("Method not allowed", 405);
+    }
+
+    let merge: TaskMerge = match req.json().await {
+        Ok(merge) => merge,
+        Err(e) => return Response::error(format!("合并数据格式无效: {}", e), 400),
+    };
+    if merge.from.trim().is_empty() || merge.into.trim().is_empty() {
+        return Response::error("from 和 into 不能为空", 400);
+    }
+
+    let db = env.d1("DB")?;
+    let db_client = DatabaseClient::new(&db);
+    db_client.migrate().await
+        .map_err(|e| Error::RustError(format!("执行数据库迁移失败: {}", e)))?;
+
+    let result = db_client.merge_task_records(merge.from.trim(), merge.into.trim()).await
+        .map(|task| serde_json::json!({
+            "success": true,
+            "merged": merge.from,
+            "task": task
+        }));
+
+    json_result(result)
+}
+
+/// 任务合并请求
+#[derive(Deserialize)]
+struct TaskMerge {
+    from: String,
+    into: String,
+}
+
+/// 查看（GET，?repo= 时返回该仓库按当前规则得到的项目代号）、添加或更新（POST）、删除（DELETE ?id=）项目代号规则
+/// POST 请求体：{"kind": "repo", "pattern": "owner/repo", "replacement": "代号"}
+/// 或 {"kind": "regex", "pattern": "_flutter$", "replacement": "", "position": 1}
+pub async fn handle_project_codes(req: &mut Request, env: &Env, url: &Url) -> Result<Response> {
+    if !is_authorized(req, env) {
+        return Response::error("Unauthorized", 401);
+    }
+
+    let db = env.d1("DB")?;
+    let db_client = DatabaseClient::new(&db);
+    db_client.migrate().await
+        .map_err(|e| Error::RustError(format!("执行数据库迁移失败: {}", e)))?;
+
+    let result = match req.method() {
+        Method::Get => {
+            let repo = query_param(url, "repo");
+            db_client.list_project_code_rules().await
+                .and_then(|rules| {
+                    let code = match &repo {
+                        Some(repo) => Some(ProjectCodes::new(&rules)?.code_for(repo)),
+                        None => None,
+                    };
+                    Ok(serde_json::json!({
+                        "success": true,
+                        "rules": rules,
+                        "repo": repo,
+                        "code": code
+                    }))
+                })
+        }
+        Method::Post => {
+            let rule: ProjectCodeRule = match req.json().await {
+                Ok(rule) => rule,
+                Err(e) => return Response::error(format!("项目代号规则格式无效: {}", e), 400),
+            };
+            if let Err(e) = rule.validate() {
+                return Response::error(e.to_string(), 400);
+            }
+            db_client.save_project_code_rule(&rule).await
+                .map(|_| serde_json::json!({
+                    "success": true,
+                    "rule": rule
+                }))
+        }
+        Method::Delete => {
+            let id = match query_param(url, "id").and_then(|value| value.parse::<i64>().ok()) {
+                Some(id) => id,
+                None => return Response::error("缺少 id 参数", 400),
+            };
+            db_client.delete_project_code_rule(id).await
+                .map(|_| serde_json::json!({
+                    "success": true,
+                    "id": id
+                }))
+        }
+        _ => return Response::error("Method not allowed", 405),
+    };
+
+    json_result(result)
+}
+
+/// 导出所有持久化数据：默认返回 JSON，?format=csv&table= 返回单张表的 CSV
+pub async fn handle_export(req: &Request, env: &Env, url: &Url) -> Result<Response> {
+    if !is_authorized(req, env) {
+        return Response::error("Unauthorized", 401);
+    }
+    if req.method() != Method::Get {
+        return Response::error("Method not allowed", 405);
+    }
+
+    let db = env.d1("DB")?;
+    let db_client = DatabaseClient::new(&db);
+    db_client.migrate().await
+        .map_err(|e| Error::RustError(format!("执行数据库迁移失败: {}", e)))?;
+
+    match query_param(url, "format").as_deref() {
+        None | Some("json") => {
+            let result = db_client.export_backup().await
+                .and_then(|backup| serde_json::to_value(backup).map_err(|e| anyhow::anyhow!("序列化备份失败: {}", e)));
+            json_result(result)
+        }
+        Some("csv") => {
+            let table = match query_param(url, "table").as_deref().and_then(backup_table) {
+                Some(table) => table,
+                None => {
+                    let names: Vec<&str> = BACKUP_TABLES.iter().map(|table| table.name).collect();
+                    return Response::error(format!("CSV 导出需要 table 参数，可选: {}", names.join(", ")), 400);
+                }
+            };
+            match db_client.export_table(table).await {
+                Ok(rows) => {
+                    let mut response = Response::ok(to_csv(table, &rows))?;
+                    response.headers_mut().set("Content-Type", "text/csv; charset=utf-8")?;
+                    response.headers_mut().set("Content-Disposition", &format!("attachment; filename=\"{}.csv\"", table.name))?;
+                    Ok(response)
+                }
+                Err(e) => json_result(Err(e)),
+            }
+        }
+        Some(_) => Response::error("format 参数无效，可选 json 或 csv", 400),
+    }
+}
+
+/// 导入 /admin/export 导出的 JSON 数据
+/// ?on_conflict=skip|replace|fail 指定主键冲突时的处理方式（默认 skip），?dry_run=true 时只校验不写入
+pub async fn handle_import(req: &mut Request, env: &Env, url: &Url) -> Result<Response> {
+    if !is_authorized(req, env) {
+        return Response::error("Unauthorized", 401);
+    }
+    if req.method() != Method::Post {
+        return Response::error("Method not allowed", 405);
+    }
+
+    let mode = match query_param(url, "on_conflict") {
+        Some(value) => match ConflictMode::parse(&value) {
+            Some(mode) => mode,
+            None => return Response::error("on_conflict 参数无效，可选 skip、replace 或 fail", 400),
+        },
+        None => ConflictMode::default(),
+    };
+    let dry_run = query_param(url, "dry_run").map(|value| value == "true").unwrap_or(false);
+
+    let backup: Backup = match req.json().await {
+        Ok(backup) => backup,
+        Err(e) => return Response::error(format!("备份数据格式无效: {}", e), 400),
+    };
+    if let Err(e) = backup.validate() {
+        return Response::error(e.to_string(), 400);
+    }
+
+    if dry_run {
+        return Response::from_json(&serde_json::json!({
+            "success": true,
+            "dry_run": true,
+            "tables": backup.row_counts()
+        }));
+    }
+
+    let db = env.d1("DB")?;
+    let db_client = DatabaseClient::new(&db);
+    db_client.migrate().await
+        .map_err(|e| Error::RustError(format!("执行数据库迁移失败: {}", e)))?;
+
+    let result = db_client.import_backup(&backup, mode).await
+        .map(|summary| serde_json::json!({
+            "success": true,
+            "imported": summary.imported,
+            "skipped": summary.skipped,
+            "tables": backup.row_counts()
+        }));
+
+    json_result(result)
+}
@@ -1,7 +1,10 @@
 use serde::{Deserialize, Serialize};
 use anyhow::{Result, anyhow};
+use futures::stream::{LocalBoxStream, StreamExt};
 use worker::*;
 
+use crate::locale::Locale;
+
 #[derive(Serialize)]
 struct ChatMessage {
     role: String,
@@ -14,11 +17,25 @@ struct ChatCompletionRequest {
     messages: Vec<ChatMessage>,
     max_tokens: u32,
     temperature: f32,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<StreamOptions>,
+}
+
+/// 流式请求选项，要求接口在最后一个分块中返回 token 用量
+#[derive(Serialize)]
+struct StreamOptions {
+    include_usage: bool,
 }
 
 #[derive(Deserialize)]
 struct ChatCompletionResponse {
+    #[serde(default)]
+    model: Option<String>,
     choices: Vec<Choice>,
+    #[serde(default)]
+    usage: Option<TokenUsage>,
 }
 
 #[derive(Deserialize)]
@@ -31,28 +48,252 @@ struct Message {
     content: String,
 }
 
+#[derive(Deserialize)]
+struct ChatCompletionChunk {
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    choices: Vec<ChunkChoice>,
+    #[serde(default)]
+    usage: Option<TokenUsage>,
+}
+
+#[derive(Deserialize)]
+struct ChunkChoice {
+    #[serde(default)]
+    delta: Option<Delta>,
+}
+
+#[derive(Deserialize)]
+struct Delta {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+/// SSE 流解析器，将 OpenAI 兼容接口的 `data:` 分块解析为增量文本
+/// 网络分块可能在任意字节处截断（包括 UTF-8 多字节字符中间），因此按完整行解析
+#[derive(Default)]
+pub struct SseParser {
+    buffer: Vec<u8>,
+    done: bool,
+    model: Option<String>,
+    usage: Option<TokenUsage>,
+}
+
+impl SseParser {
+    /// 输入一个网络分块，返回其中完整事件包含的增量文本
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<Result<String>> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut tokens = Vec::new();
+        while let Some(newline) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=newline).collect();
+            if let Some(token) = self.parse_line(&line) {
+                tokens.push(token);
+            }
+        }
+        tokens
+    }
+
+    /// 流结束时处理缓冲区中剩余的最后一行
+    pub fn finish(&mut self) -> Vec<Result<String>> {
+        let line = std::mem::take(&mut self.buffer);
+        self.parse_line(&line).into_iter().collect()
+    }
+
+    /// 流中返回的模型名和 token 用量，接口未返回时为 None
+    pub fn take_usage(&mut self) -> (Option<String>, Option<TokenUsage>) {
+        (self.model.take(), self.usage.take())
+    }
+
+    fn parse_line(&mut self, line: &[u8]) -> Option<Result<String>> {
+        if self.done {
+            return None;
+        }
+
+        let line = String::from_utf8_lossy(line);
+        let data = line.trim().strip_prefix("data:")?.trim();
+        if data.is_empty() {
+            return None;
+        }
+        if data == "[DONE]" {
+            self.done = true;
+            return None;
+        }
+
+        match serde_json::from_str::<ChatCompletionChunk>(data) {
+            Ok(chunk) => {
+                if chunk.model.is_some() {
+                    self.model = chunk.model;
+                }
+                if chunk.usage.is_some() {
+                    self.usage = chunk.usage;
+                }
+                let content: String = chunk.choices.into_iter()
+                    .filter_map(|choice| choice.delta.and_then(|delta| delta.content))
+                    .collect();
+                if content.is_empty() {
+                    None
+                } else {
+                    Some(Ok(content))
+                }
+            }
+            Err(e) => Some(Err(anyhow!("解析 AI 流式响应失败: {}", e))),
+        }
+    }
+}
+
+/// AI 接口返回的 token 用量
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TokenUsage {
+    #[serde(default)]
+    pub prompt_tokens: u32,
+    #[serde(default)]
+    pub completion_tokens: u32,
+    #[serde(default)]
+    pub total_tokens: u32,
+}
+
+/// 一次 AI 生成的结果及其用量信息
+#[derive(Debug, Clone)]
+pub struct AICompletion {
+    pub content: String,
+    pub model: String,
+    pub usage: TokenUsage,
+    pub latency_ms: u64,
+}
+
+/// 流式生成的输出
+pub enum StreamChunk {
+    /// 增量文本
+    Token(String),
+    /// 生成结束，包含完整输出和用量
+    Done(AICompletion),
+}
+
 /// AI 客户端，用于与 OpenAI 兼容的 API 通信
 pub struct AIClient {
     api_key: String,
     base_url: String,
     model: String,
+    locale: Locale,
 }
 
 impl AIClient {
     /// 创建新的 AI 客户端
-    pub fn new(api_key: String, base_url: String, model: String) -> Self {
+    pub fn new(api_key: String, base_url: String, model: String, locale: Locale) -> Self {
         Self {
             api_key,
             base_url,
             model,
+            locale,
         }
     }
 
     /// 生成每日站会报告
-    pub async fn generate_standup_report(&self, prompt: &str) -> Result<String> {
+    pub async fn generate_standup_report(&self, prompt: &str) -> Result<AICompletion> {
+        self.complete(self.locale.texts().system_prompt, prompt).await
+    }
+
+    /// 生成周报或月报
+    pub async fn generate_summary_report(&self, prompt: &str) -> Result<AICompletion> {
+        self.complete(self.locale.texts().summary_system_prompt, prompt).await
+    }
+
+    /// 使用指定的系统提示词调用 chat/completions
+    async fn complete(&self, system_prompt: &str, prompt: &str) -> Result<AICompletion> {
+        let request = self.build_request(system_prompt, prompt, false)?;
+
+        let started_at = js_sys::Date::now();
+        let mut response = Fetch::Request(request).send().await?;
+
+        if !(200..300).contains(&response.status_code()) {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow!("AI API 请求失败: {} - {}", response.status_code(), error_text));
+        }
+
+        let completion_response: ChatCompletionResponse = response.json().await?;
+        let latency_ms = (js_sys::Date::now() - started_at).max(0.0) as u64;
+
+        let content = match completion_response.choices.first() {
+            Some(choice) => choice.message.content.trim().to_string(),
+            None => return Err(anyhow!("AI API 返回空响应")),
+        };
+
+        let mut usage = completion_response.usage.unwrap_or_default();
+        if usage.total_tokens == 0 {
+            usage.total_tokens = usage.prompt_tokens + usage.completion_tokens;
+        }
+
+        Ok(AICompletion {
+            content,
+            // 部分兼容接口不返回 model 字段，此时使用请求时的模型名
+            model: completion_response.model.unwrap_or_else(|| self.model.clone()),
+            usage,
+            latency_ms,
+        })
+    }
+
+    /// 以流式方式生成每日站会报告，逐段返回 AI 输出的文本，最后返回完整输出和用量
+    pub async fn stream_standup_report(&self, prompt: &str) -> Result<LocalBoxStream<'static, Result<StreamChunk>>> {
+        let request = self.build_request(self.locale.texts().system_prompt, prompt, true)?;
+
+        let started_at = js_sys::Date::now();
+        let mut response = Fetch::Request(request).send().await?;
+
+        if !(200..300).contains(&response.status_code()) {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow!("AI API 请求失败: {} - {}", response.status_code(), error_text));
+        }
+
+        let mut parser = SseParser::default();
+        let mut content = String::new();
+        let request_model = self.model.clone();
+        let byte_stream = response.stream()?
+            .map(Some)
+            .chain(futures::stream::once(async { None }));
+
+        let tokens = byte_stream
+            .map(move |chunk| {
+                let finished = chunk.is_none();
+                let mut items = match chunk {
+                    Some(Ok(bytes)) => parser.push(&bytes),
+                    Some(Err(e)) => vec![Err(anyhow!("读取 AI 流式响应失败: {:?}", e))],
+                    None => parser.finish(),
+                };
+                for token in items.iter().flatten() {
+                    content.push_str(token);
+                }
+
+                let mut chunks: Vec<Result<StreamChunk>> = items.drain(..)
+                    .map(|item| item.map(StreamChunk::Token))
+                    .collect();
+                if finished {
+                    let (model, usage) = parser.take_usage();
+                    let mut usage = usage.unwrap_or_default();
+                    if usage.total_tokens == 0 {
+                        usage.total_tokens = usage.prompt_tokens + usage.completion_tokens;
+                    }
+                    chunks.push(Ok(StreamChunk::Done(AICompletion {
+                        content: std::mem::take(&mut content).trim().to_string(),
+                        model: model.unwrap_or_else(|| request_model.clone()),
+                        usage,
+                        latency_ms: (js_sys::Date::now() - started_at).max(0.0) as u64,
+                    })));
+                }
+                chunks
+            })
+            .flat_map(futures::stream::iter)
+            .boxed_local();
+
+        Ok(tokens)
+    }
+
+    /// 构造 chat/completions 请求
+    fn build_request(&self, system_prompt: &str, prompt: &str, stream: bool) -> Result<Request> {
         let system_message = ChatMessage {
             role: "system".to_string(),
-            content: "你是一个专业的项目管理助手，专门帮助生成每日站会报告。请严格按照用户提供的格式要求，基于 GitHub PR 数据生成简洁、专业的站会内容，并且不要输出多余的内容。".to_string(),
+            content: system_prompt.to_string(),
         };
 
         let user_message = ChatMessage {
@@ -65,6 +306,8 @@ impl AIClient {
             messages: vec![system_message, user_message],
             max_tokens: 1000,
             temperature: 0.3,
+            stream,
+            stream_options: stream.then_some(StreamOptions { include_usage: true }),
         };
 
         let url = format!("{}/chat/completions", self.base_url);
@@ -73,27 +316,62 @@ impl AIClient {
         let mut headers = worker::Headers::new();
         headers.set("Content-Type", "application/json")?;
         headers.set("Authorization", &format!("Bearer {}", self.api_key))?;
+        if stream {
+            headers.set("Accept", "text/event-stream")?;
+        }
 
         let mut request_init = RequestInit::new();
         request_init.method = Method::Post;
         request_init.headers = headers;
         request_init.body = Some(serde_json::to_string(&request_body)?.into());
 
-        let request = Request::new_with_init(&url, &request_init)?;
+        Ok(Request::new_with_init(&url, &request_init)?)
+    }
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let mut response = Fetch::Request(request).send().await?;
+    fn tokens(items: Vec<Result<String>>) -> Vec<String> {
+        items.into_iter().map(|item| item.unwrap()).collect()
+    }
 
-        if !(200..300).contains(&response.status_code()) {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(anyhow!("AI API 请求失败: {} - {}", response.status_code(), error_text));
-        }
+    #[test]
+    fn parses_tokens_split_across_chunks_and_utf8_boundaries() {
+        let event = "data: {\"choices\":[{\"delta\":{\"content\":\"修复\"}}]}\n\n".as_bytes();
+        let (head, tail) = event.split_at(40);
+        let mut parser = SseParser::default();
 
-        let completion_response: ChatCompletionResponse = response.json().await?;
+        assert!(parser.push(head).is_empty());
+        assert_eq!(tokens(parser.push(tail)), vec!["修复"]);
+    }
 
-        if let Some(choice) = completion_response.choices.first() {
-            Ok(choice.message.content.trim().to_string())
-        } else {
-            Err(anyhow!("AI API 返回空响应"))
-        }
+    #[test]
+    fn ignores_events_after_done_and_reads_usage() {
+        let mut parser = SseParser::default();
+        let items = parser.push(concat!(
+            "data: {\"model\":\"gpt-x\",\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"完成\"}}]}\n\n",
+            ": keep-alive\n\n",
+            "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":10,\"completion_tokens\":2,\"total_tokens\":12}}\n\n",
+            "data: [DONE]\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"多余\"}}]}\n\n",
+        ).as_bytes());
+        assert_eq!(tokens(items), vec!["完成"]);
+
+        let (model, usage) = parser.take_usage();
+        assert_eq!(model.as_deref(), Some("gpt-x"));
+        assert_eq!(usage.map(|usage| usage.total_tokens), Some(12));
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn finish_parses_last_line_without_newline_and_reports_invalid_json() {
+        let mut parser = SseParser::default();
+        assert!(parser.push(b"data: {\"choices\":[{\"delta\":{\"content\":\"end\"}}]}").is_empty());
+        assert_eq!(tokens(parser.finish()), vec!["end"]);
+
+        let mut parser = SseParser::default();
+        let items = parser.push(b"data: {not json}\n");
+        assert!(items[0].is_err());
+    }
+}
@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use anyhow::{Result, anyhow};
+
+use crate::ai_client::TokenUsage;
+
+/// 单个模型的价格（每百万 tokens）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelPrice {
+    #[serde(default)]
+    pub prompt: f64,
+    #[serde(default)]
+    pub completion: f64,
+}
+
+/// 模型价格表，来自 AI_PRICE_TABLE 环境变量
+/// 格式：{"THUDM/glm-4-9b-chat": {"prompt": 0.6, "completion": 0.6}}
+#[derive(Debug, Clone, Default)]
+pub struct PriceTable {
+    prices: HashMap<String, ModelPrice>,
+    currency: String,
+}
+
+impl PriceTable {
+    /// 从 JSON 字符串解析价格表
+    pub fn from_json(json: &str, currency: String) -> Result<Self> {
+        let prices: HashMap<String, ModelPrice> = serde_json::from_str(json)
+            .map_err(|e| anyhow!("解析 AI 价格表失败: {}", e))?;
+
+        Ok(Self { prices, currency })
+    }
+
+    /// 空价格表，所有模型成本按 0 计算
+    pub fn empty(currency: String) -> Self {
+        Self {
+            prices: HashMap::new(),
+            currency,
+        }
+    }
+
+    /// 计费币种
+    pub fn currency(&self) -> &str {
+        &self.currency
+    }
+
+    /// 估算一次调用的成本
+    /// 接口返回的模型名可能带有版本后缀，找不到精确匹配时按前缀匹配
+    pub fn estimate_cost(&self, model: &str, usage: &TokenUsage) -> f64 {
+        let price = self.prices.get(model).or_else(|| {
+            self.prices
+                .iter()
+                .filter(|(name, _)| model.starts_with(name.as_str()))
+                .max_by_key(|(name, _)| name.len())
+                .map(|(_, price)| price)
+        });
+
+        match price {
+            Some(price) => {
+                (usage.prompt_tokens as f64 * price.prompt
+                    + usage.completion_tokens as f64 * price.completion)
+                    / 1_000_000.0
+            }
+            None => 0.0,
+        }
+    }
+}
+
+/// 单次 AI 调用的用量记录
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AIUsageRecord {
+    pub user_login: String,
+    pub run_date: String,         // 运行日期 YYYY-MM-DD
+    pub model: String,
+    pub prompt_tokens: i32,
+    pub completion_tokens: i32,
+    pub total_tokens: i32,
+    pub latency_ms: i32,
+    pub estimated_cost: f64,
+    pub currency: String,
+}
+
+/// 按用户汇总的月度用量
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MonthlyUsageSummary {
+    pub user_login: String,
+    pub runs: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub total_tokens: i64,
+    pub avg_latency_ms: f64,
+    pub total_cost: f64,
+    pub currency: String,
+}
@@ -0,0 +1,406 @@
+use std::collections::BTreeMap;
+use chrono::NaiveDate;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::database::{TaskStatus, TaskStatusSource};
+use crate::migrations::MIGRATIONS;
+use crate::project_codes::RuleKind;
+
+/// 备份文件格式版本，格式不兼容时递增
+pub const BACKUP_FORMAT_VERSION: i32 = 1;
+
+/// 可导出和导入的表
+pub struct BackupTable {
+    pub name: &'static str,
+    /// 导出和导入的列
+    pub columns: &'static [&'static str],
+    /// 导入时不能为空的列
+    pub required: &'static [&'static str],
+    /// 格式为 YYYY-MM-DD 的列
+    pub dates: &'static [&'static str],
+    /// 由数据库生成的自增 id，导出但导入时忽略，避免与目标数据库中的其他行冲突
+    pub generated: &'static [&'static str],
+    /// 没有主键或唯一约束可以判断重复时，用这些列判断导入的行是否已存在
+    pub natural_key: &'static [&'static str],
+}
+
+/// 所有持久化数据，按导入顺序排列；新增表时在这里追加
+pub const BACKUP_TABLES: &[BackupTable] = &[
+    // 任务
+    BackupTable {
+        name: "taiga_tasks",
+        columns: &["task_key", "first_seen_date", "last_seen_date", "total_days", "status", "extra_days", "status_source", "created_at", "updated_at"],
+        required: &["task_key", "first_seen_date", "last_seen_date"],
+        dates: &["first_seen_date", "last_seen_date"],
+        generated: &[],
+        natural_key: &[],
+    },
+    BackupTable {
+        name: "archived_tasks",
+        columns: &["task_key", "first_seen_date", "last_seen_date", "total_days", "status", "extra_days", "status_source", "archived_at"],
+        required: &["task_key", "first_seen_date", "last_seen_date"],
+        dates: &["first_seen_date", "last_seen_date"],
+        generated: &[],
+        natural_key: &[],
+    },
+    BackupTable {
+        name: "task_activity",
+        columns: &["task_key", "activity_date"],
+        required: &["task_key", "activity_date"],
+        dates: &["activity_date"],
+        generated: &[],
+        natural_key: &[],
+    },
+    BackupTable {
+        name: "pr_task_links",
+        columns: &["repo", "pr_number", "task_key", "first_linked_date", "merged_at", "created_at"],
+        required: &["repo", "pr_number", "task_key", "first_linked_date"],
+        dates: &["first_linked_date"],
+        generated: &[],
+        natural_key: &[],
+    },
+    // 报告和运行记录
+    BackupTable {
+        name: "standup_reports",
+        columns: &["id", "login", "report_date", "report", "raw_data", "entries", "created_at"],
+        required: &["login", "report_date", "report", "raw_data"],
+        dates: &["report_date"],
+        generated: &["id"],
+        natural_key: &["login", "report_date", "created_at"],
+    },
+    BackupTable {
+        name: "standup_runs",
+        columns: &["login", "run_date", "status", "report", "channels", "error", "attempts", "created_at", "updated_at"],
+        required: &["login", "run_date", "status"],
+        dates: &["run_date"],
+        generated: &[],
+        natural_key: &[],
+    },
+    BackupTable {
+        name: "summary_runs",
+        columns: &["login", "period", "period_end", "status", "error", "attempts", "created_at", "updated_at"],
+        required: &["login", "period", "period_end", "status"],
+        dates: &["period_end"],
+        generated: &[],
+        natural_key: &[],
+    },
+    BackupTable {
+        name: "digest_runs",
+        columns: &["digest_date", "status", "report", "error", "attempts", "created_at", "updated_at"],
+        required: &["digest_date", "status"],
+        dates: &["digest_date"],
+        generated: &[],
+        natural_key: &[],
+    },
+    BackupTable {
+        name: "ai_usage",
+        columns: &["id", "user_login", "run_date", "model", "prompt_tokens", "completion_tokens", "total_tokens", "latency_ms", "estimated_cost", "currency", "created_at"],
+        required: &["user_login", "run_date", "model"],
+        dates: &["run_date"],
+        generated: &["id"],
+        natural_key: &["user_login", "run_date", "model", "created_at"],
+    },
+    // 配置
+    BackupTable {
+        name: "team_members",
+        columns: &["login", "display_name", "calendar", "timezone", "send_time", "created_at", "updated_at"],
+        required: &["login"],
+        dates: &[],
+        generated: &[],
+        natural_key: &[],
+    },
+    BackupTable {
+        name: "ics_calendars",
+        columns: &["name", "content", "updated_at"],
+        required: &["name", "content"],
+        dates: &[],
+        generated: &[],
+        natural_key: &[],
+    },
+    BackupTable {
+        name: "leave",
+        columns: &["id", "login", "start_date", "end_date", "reason", "source", "created_at"],
+        required: &["login", "start_date", "end_date"],
+        dates: &["start_date", "end_date"],
+        generated: &["id"],
+        natural_key: &["login", "start_date", "end_date"],
+    },
+    BackupTable {
+        name: "holiday_calendar",
+        columns: &["date", "year", "status", "name", "source"],
+        required: &["date", "year", "status"],
+        dates: &["date"],
+        generated: &[],
+        natural_key: &[],
+    },
+    BackupTable {
+        name: "holiday_calendar_years",
+        columns: &["year", "source", "imported_at"],
+        required: &["year", "source"],
+        dates: &[],
+        generated: &[],
+        natural_key: &[],
+    },
+    BackupTable {
+        name: "project_code_rules",
+        columns: &["id", "kind", "pattern", "replacement", "position", "created_at", "updated_at"],
+        required: &["kind", "pattern"],
+        dates: &[],
+        generated: &["id"],
+        natural_key: &[],
+    },
+];
+
+/// 按名称查找可备份的表
+pub fn backup_table(name: &str) -> Option<&'static BackupTable> {
+    BACKUP_TABLES.iter().find(|table| table.name == name)
+}
+
+/// 当前数据库结构的迁移版本
+pub fn current_schema_version() -> i32 {
+    MIGRATIONS.last().map(|migration| migration.version).unwrap_or(0)
+}
+
+/// 一行数据，列名到值
+pub type BackupRow = Map<String, Value>;
+
+/// 导出的完整数据
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Backup {
+    pub format_version: i32,
+    pub schema_version: i32,
+    #[serde(default)]
+    pub exported_at: Option<String>,
+    pub tables: BTreeMap<String, Vec<BackupRow>>,
+}
+
+impl Backup {
+    /// 校验导入数据：格式和结构版本兼容、表和列都已知、必填列不为空、日期和任务状态格式正确
+    pub fn validate(&self) -> Result<()> {
+        if self.format_version != BACKUP_FORMAT_VERSION {
+            return Err(anyhow!("不支持的备份格式版本 {}，当前版本为 {}", self.format_version, BACKUP_FORMAT_VERSION));
+        }
+        let schema_version = current_schema_version();
+        if self.schema_version > schema_version {
+            return Err(anyhow!("备份来自更新的数据库结构（版本 {}），当前版本为 {}，请先升级 Worker", self.schema_version, schema_version));
+        }
+
+        for (name, rows) in &self.tables {
+            let table = backup_table(name)
+                .ok_or_else(|| anyhow!("未知的表: {}", name))?;
+            for (index, row) in rows.iter().enumerate() {
+                validate_row(table, row)
+                    .map_err(|e| anyhow!("{} 第 {} 行: {}", name, index + 1, e))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 备份中各表的行数
+    pub fn row_counts(&self) -> BTreeMap<String, usize> {
+        self.tables.iter()
+            .map(|(name, rows)| (name.clone(), rows.len()))
+            .collect()
+    }
+}
+
+fn validate_row(table: &BackupTable, row: &BackupRow) -> Result<()> {
+    for (column, value) in row {
+        if !table.columns.contains(&column.as_str()) {
+            return Err(anyhow!("未知的列: {}", column));
+        }
+        if value.is_array() || value.is_object() {
+            return Err(anyhow!("列 {} 的值必须是字符串、数字、布尔值或 null", column));
+        }
+    }
+
+    for column in table.required {
+        if row.get(*column).map(Value::is_null).unwrap_or(true) {
+            return Err(anyhow!("缺少必填列: {}", column));
+        }
+    }
+
+    for column in table.dates {
+        if let Some(value) = row.get(*column).filter(|value| !value.is_null()) {
+            let valid = value.as_str()
+                .map(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok())
+                .unwrap_or(false);
+            if !valid {
+                return Err(anyhow!("列 {} 的日期格式无效: {}，请使用 YYYY-MM-DD 格式", column, value));
+            }
+        }
+    }
+
+    if table.name == "taiga_tasks" || table.name == "archived_tasks" {
+        if let Some(status) = row.get("status").filter(|value| !value.is_null()) {
+            if status.as_str().and_then(TaskStatus::parse).is_none() {
+                return Err(anyhow!("status 无效: {}，可选 open 或 done", status));
+            }
+        }
+        if let Some(source) = row.get("status_source").filter(|value| !value.is_null()) {
+            if source.as_str().and_then(TaskStatusSource::parse).is_none() {
+                return Err(anyhow!("status_source 无效: {}，可选 auto 或 manual", source));
+            }
+        }
+    }
+
+    if table.name == "project_code_rules" {
+        if let Some(kind) = row.get("kind") {
+            if kind.as_str().and_then(RuleKind::parse).is_none() {
+                return Err(anyhow!("kind 无效: {}，可选 repo 或 regex", kind));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// 导入结果：写入的行数和因已存在而跳过的行数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ImportSummary {
+    pub imported: usize,
+    pub skipped: usize,
+}
+
+/// 导入时遇到主键冲突的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictMode {
+    /// 保留已有数据，跳过冲突的行
+    #[default]
+    Skip,
+    /// 用导入的数据覆盖已有数据
+    Replace,
+    /// 遇到冲突时整个导入失败并回滚
+    Fail,
+}
+
+impl ConflictMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "skip" => Some(ConflictMode::Skip),
+            "replace" => Some(ConflictMode::Replace),
+            "fail" => Some(ConflictMode::Fail),
+            _ => None,
+        }
+    }
+
+    /// 对应的 INSERT 语句前缀
+    pub fn insert_clause(&self) -> &'static str {
+        match self {
+            ConflictMode::Skip => "INSERT OR IGNORE",
+            ConflictMode::Replace => "INSERT OR REPLACE",
+            ConflictMode::Fail => "INSERT",
+        }
+    }
+}
+
+/// 将一张表导出为 CSV，第一行为列名
+pub fn to_csv(table: &BackupTable, rows: &[BackupRow]) -> String {
+    let mut csv = table.columns.join(",");
+    csv.push_str("\r\n");
+
+    for row in rows {
+        let fields: Vec<String> = table.columns.iter()
+            .map(|column| match row.get(*column) {
+                None | Some(Value::Null) => String::new(),
+                Some(Value::String(value)) => csv_field(value),
+                Some(value) => csv_field(&value.to_string()),
+            })
+            .collect();
+        csv.push_str(&fields.join(","));
+        csv.push_str("\r\n");
+    }
+
+    csv
+}
+
+/// 包含逗号、引号或换行的字段加引号，引号转义为两个引号
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn backup(tables: Value) -> Backup {
+        serde_json::from_value(json!({
+            "format_version": BACKUP_FORMAT_VERSION,
+            "schema_version": current_schema_version(),
+            "tables": tables,
+        })).unwrap()
+    }
+
+    fn error(backup: &Backup) -> String {
+        backup.validate().unwrap_err().to_string()
+    }
+
+    #[test]
+    fn accepts_valid_backup() {
+        let backup = backup(json!({
+            "taiga_tasks": [{"task_key": "acme#1", "first_seen_date": "2025-01-02", "last_seen_date": "2025-01-03", "status": "done"}],
+            "task_activity": [{"task_key": "acme#1", "activity_date": "2025-01-02"}],
+        }));
+        assert!(backup.validate().is_ok());
+        assert_eq!(backup.row_counts().get("task_activity"), Some(&1));
+    }
+
+    #[test]
+    fn rejects_incompatible_versions() {
+        let mut newer = backup(json!({}));
+        newer.schema_version = current_schema_version() + 1;
+        assert!(error(&newer).contains("更新的数据库结构"));
+
+        let mut other_format = backup(json!({}));
+        other_format.format_version = BACKUP_FORMAT_VERSION + 1;
+        assert!(error(&other_format).contains("不支持的备份格式版本"));
+    }
+
+    #[test]
+    fn rejects_invalid_rows() {
+        assert!(error(&backup(json!({"unknown": []}))).contains("未知的表"));
+        assert!(error(&backup(json!({"task_activity": [{"task_key": "acme#1", "activity_date": "2025-01-02", "extra": 1}]})))
+            .contains("task_activity 第 1 行: 未知的列: extra"));
+        assert!(error(&backup(json!({"task_activity": [{"task_key": "acme#1", "activity_date": null}]})))
+            .contains("缺少必填列: activity_date"));
+        assert!(error(&backup(json!({"task_activity": [{"task_key": "acme#1", "activity_date": "2025/01/02"}]})))
+            .contains("日期格式无效"));
+        assert!(error(&backup(json!({"task_activity": [{"task_key": ["acme#1"], "activity_date": "2025-01-02"}]})))
+            .contains("task_key 的值必须是"));
+        assert!(error(&backup(json!({"taiga_tasks": [{"task_key": "acme#1", "first_seen_date": "2025-01-02", "last_seen_date": "2025-01-03", "status": "closed"}]})))
+            .contains("status 无效"));
+    }
+
+    #[test]
+    fn csv_quotes_special_fields_and_leaves_nulls_empty() {
+        let table = backup_table("pr_task_links").unwrap();
+        let row = json!({
+            "repo": "acme/web",
+            "pr_number": 7,
+            "task_key": "acme#1",
+            "first_linked_date": "2025-01-02",
+            "merged_at": null,
+            "created_at": "say \"hi\", then\nleave",
+        });
+        let csv = to_csv(table, &[row.as_object().unwrap().clone()]);
+        assert_eq!(csv, concat!(
+            "repo,pr_number,task_key,first_linked_date,merged_at,created_at\r\n",
+            "acme/web,7,acme#1,2025-01-02,,\"say \"\"hi\"\", then\nleave\"\r\n",
+        ));
+    }
+
+    #[test]
+    fn parses_conflict_modes() {
+        assert_eq!(ConflictMode::parse(" Replace "), Some(ConflictMode::Replace));
+        assert_eq!(ConflictMode::parse("merge"), None);
+        assert_eq!(ConflictMode::Skip.insert_clause(), "INSERT OR IGNORE");
+    }
+}
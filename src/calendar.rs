@@ -0,0 +1,289 @@
+use std::collections::{HashMap, HashSet};
+use chrono::{Datelike, NaiveDate};
+use anyhow::{Result, anyhow};
+use futures::future::LocalBoxFuture;
+
+use crate::database::DatabaseClient;
+use crate::holiday_calendar::{DayStatus, HolidayCalendar};
+
+/// 工作日日历，不同地区的成员可以使用不同的实现
+pub trait WorkingCalendar {
+    /// 查询指定日期的状态
+    fn day_status<'b>(&'b self, date: NaiveDate) -> LocalBoxFuture<'b, Result<DayStatus>>;
+
+    /// 查询日期范围内（包含首尾两天）需要上班的日期，默认逐日查询
+    fn working_days<'b>(&'b self, start: NaiveDate, end: NaiveDate) -> LocalBoxFuture<'b, Result<HashSet<NaiveDate>>> {
+        Box::pin(async move {
+            let mut days = HashSet::new();
+            let mut current = start;
+            while current <= end {
+                if self.day_status(current).await?.is_working_day() {
+                    days.insert(current);
+                }
+                current = match current.succ_opt() {
+                    Some(next) => next,
+                    None => break,
+                };
+            }
+            Ok(days)
+        })
+    }
+}
+
+/// 成员可选择的日历类型，配置格式：
+/// - `cn`：中国大陆法定节假日（本地日历 + 节假日 API 补全）
+/// - `ics:<名称>`：通过 /calendars/ics 上传的 ICS 文件，例如 `ics:hk`、`ics:sg`、`ics:de-berlin`
+/// - `weekend`：只排除周末
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CalendarKind {
+    China,
+    Ics(String),
+    WeekendOnly,
+}
+
+impl CalendarKind {
+    pub fn parse(value: &str) -> Result<Self> {
+        let value = value.trim();
+        match value.to_lowercase().as_str() {
+            "weekend" => Ok(CalendarKind::WeekendOnly),
+            "" | "cn" | "china" => Ok(CalendarKind::China),
+            _ => match value.split_once(':') {
+                Some((prefix, name)) if prefix.eq_ignore_ascii_case("ics") && !name.trim().is_empty() => {
+                    Ok(CalendarKind::Ics(name.trim().to_string()))
+                }
+                _ => Err(anyhow!("无效的日历配置: {}，可选 cn、weekend 或 ics:<名称>", value)),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for CalendarKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CalendarKind::China => write!(f, "cn"),
+            CalendarKind::Ics(name) => write!(f, "ics:{}", name),
+            CalendarKind::WeekendOnly => write!(f, "weekend"),
+        }
+    }
+}
+
+/// 根据日历类型创建日历
+pub async fn create_calendar<'a>(
+    kind: &CalendarKind,
+    db_client: &'a DatabaseClient<'a>,
+    holiday_api_url: Option<String>,
+) -> Result<Box<dyn WorkingCalendar + 'a>> {
+    match kind {
+        CalendarKind::China => Ok(Box::new(HolidayCalendar::new(db_client, holiday_api_url))),
+        CalendarKind::WeekendOnly => Ok(Box::new(WeekendCalendar)),
+        CalendarKind::Ics(name) => {
+            let content = db_client.get_ics_calendar(name).await?
+                .ok_or_else(|| anyhow!("未找到 ICS 日历: {}", name))?;
+            Ok(Box::new(IcsCalendar::parse(&content)?))
+        }
+    }
+}
+
+impl<'a> WorkingCalendar for HolidayCalendar<'a> {
+    fn day_status<'b>(&'b self, date: NaiveDate) -> LocalBoxFuture<'b, Result<DayStatus>> {
+        Box::pin(HolidayCalendar::day_status(self, date))
+    }
+
+    fn working_days<'b>(&'b self, start: NaiveDate, end: NaiveDate) -> LocalBoxFuture<'b, Result<HashSet<NaiveDate>>> {
+        Box::pin(HolidayCalendar::working_days(self, start, end))
+    }
+}
+
+/// 只排除周末的默认日历
+pub struct WeekendCalendar;
+
+impl WorkingCalendar for WeekendCalendar {
+    fn day_status<'b>(&'b self, date: NaiveDate) -> LocalBoxFuture<'b, Result<DayStatus>> {
+        Box::pin(async move { Ok(DayStatus::default_for(date)) })
+    }
+}
+
+/// ICS 文件中的一个事件（日期范围包含首尾两天）
+#[derive(Debug, Clone)]
+pub struct IcsEvent {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub summary: String,
+    pub categories: String,
+}
+
+/// 解析 ICS 文本中的 VEVENT（不支持 RRULE 重复规则，需要在文件中逐条列出）
+pub fn parse_ics_events(content: &str) -> Result<Vec<IcsEvent>> {
+    // 展开折行：以空格或制表符开头的行是上一行的延续
+    let mut lines: Vec<String> = Vec::new();
+    for raw_line in content.lines() {
+        let raw_line = raw_line.trim_end_matches('\r');
+        if let Some(continuation) = raw_line.strip_prefix([' ', '\t']) {
+            if let Some(last) = lines.last_mut() {
+                last.push_str(continuation);
+                continue;
+            }
+        }
+        lines.push(raw_line.to_string());
+    }
+
+    let mut events = Vec::new();
+    let mut in_event = false;
+    let mut start: Option<NaiveDate> = None;
+    let mut end: Option<NaiveDate> = None;
+    let mut summary = String::new();
+    let mut categories = String::new();
+
+    for line in &lines {
+        let (name, value) = match line.split_once(':') {
+            Some((name, value)) => (name, value),
+            None => continue,
+        };
+        // 属性名可能带参数，例如 DTSTART;VALUE=DATE
+        let property = name.split(';').next().unwrap_or("").to_uppercase();
+
+        match (property.as_str(), value.trim()) {
+            ("BEGIN", "VEVENT") => {
+                in_event = true;
+                start = None;
+                end = None;
+                summary.clear();
+                categories.clear();
+            }
+            ("END", "VEVENT") => {
+                in_event = false;
+                let start_date = match start {
+                    Some(date) => date,
+                    None => continue,
+                };
+                events.push(IcsEvent {
+                    start: start_date,
+                    end: end.unwrap_or(start_date).max(start_date),
+                    summary: summary.clone(),
+                    categories: categories.clone(),
+                });
+            }
+            ("DTSTART", value) if in_event => start = Some(parse_ics_date(value)?),
+            ("DTEND", value) if in_event => {
+                // 全天事件和结束于零点的事件，DTEND 当天不包含在内
+                let date = parse_ics_date(value)?;
+                let exclusive = value.len() == 8 || value.get(9..15) == Some("000000");
+                end = Some(if exclusive { date.pred_opt().unwrap_or(date) } else { date });
+            }
+            ("SUMMARY", value) if in_event => summary = value.to_string(),
+            ("CATEGORIES", value) if in_event => categories = value.to_string(),
+            _ => {}
+        }
+    }
+
+    Ok(events)
+}
+
+/// 基于 ICS 文件的日历
+/// 每个 VEVENT 视为放假日期；SUMMARY 或 CATEGORIES 含有「补班」或 WORKDAY 的事件视为补班日
+pub struct IcsCalendar {
+    days: HashMap<NaiveDate, DayStatus>,
+}
+
+impl IcsCalendar {
+    /// 解析 ICS 文本
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut days = HashMap::new();
+        for event in parse_ics_events(content)? {
+            let label = format!("{} {}", event.summary, event.categories);
+            let status = if label.contains("补班") || label.to_uppercase().contains("WORKDAY") {
+                DayStatus::MakeupWorkday
+            } else {
+                DayStatus::Holiday
+            };
+
+            let mut current = event.start;
+            while current <= event.end {
+                days.insert(current, status);
+                current = match current.succ_opt() {
+                    Some(next) => next,
+                    None => break,
+                };
+            }
+        }
+
+        Ok(Self { days })
+    }
+}
+
+impl WorkingCalendar for IcsCalendar {
+    fn day_status<'b>(&'b self, date: NaiveDate) -> LocalBoxFuture<'b, Result<DayStatus>> {
+        Box::pin(async move {
+            Ok(self.days.get(&date).copied().unwrap_or_else(|| DayStatus::default_for(date)))
+        })
+    }
+}
+
+/// 解析 ICS 日期，支持 20250101 和 20250101T000000Z 两种格式
+fn parse_ics_date(value: &str) -> Result<NaiveDate> {
+    let date_part = value.get(..8).ok_or_else(|| anyhow!("ICS 日期格式无效: {}", value))?;
+    let date = NaiveDate::parse_from_str(date_part, "%Y%m%d")
+        .map_err(|_| anyhow!("ICS 日期格式无效: {}", value))?;
+    if date.year() < 1970 {
+        return Err(anyhow!("ICS 日期超出范围: {}", value));
+    }
+    Ok(date)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn all_day_dtend_is_exclusive() {
+        let events = parse_ics_events(
+            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTART;VALUE=DATE:20251001\r\nDTEND;VALUE=DATE:20251004\r\nSUMMARY:国庆节\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n",
+        ).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].start, date("2025-10-01"));
+        assert_eq!(events[0].end, date("2025-10-03"));
+        assert_eq!(events[0].summary, "国庆节");
+    }
+
+    #[test]
+    fn datetime_dtend_is_inclusive_unless_midnight() {
+        let events = parse_ics_events(concat!(
+            "BEGIN:VEVENT\nDTSTART:20250101T090000Z\nDTEND:20250102T180000Z\nEND:VEVENT\n",
+            "BEGIN:VEVENT\nDTSTART:20250105T000000Z\nDTEND:20250107T000000Z\nEND:VEVENT\n",
+        )).unwrap();
+        assert_eq!((events[0].start, events[0].end), (date("2025-01-01"), date("2025-01-02")));
+        assert_eq!((events[1].start, events[1].end), (date("2025-01-05"), date("2025-01-06")));
+    }
+
+    #[test]
+    fn missing_dtend_is_a_single_day_and_folded_lines_are_joined() {
+        let events = parse_ics_events("BEGIN:VEVENT\nDTSTART;VALUE=DATE:20250210\nSUMMARY:春节\n  补班\nEND:VEVENT\n").unwrap();
+        assert_eq!((events[0].start, events[0].end), (date("2025-02-10"), date("2025-02-10")));
+        assert_eq!(events[0].summary, "春节 补班");
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        assert!(parse_ics_events("BEGIN:VEVENT\nDTSTART:2025\nEND:VEVENT\n").is_err());
+        assert!(parse_ics_events("BEGIN:VEVENT\nDTSTART:19000101\nEND:VEVENT\n").is_err());
+    }
+
+    #[test]
+    fn ics_calendar_marks_holidays_and_makeup_workdays() {
+        let calendar = IcsCalendar::parse(concat!(
+            "BEGIN:VEVENT\nDTSTART;VALUE=DATE:20251001\nDTEND;VALUE=DATE:20251003\nSUMMARY:国庆节\nEND:VEVENT\n",
+            "BEGIN:VEVENT\nDTSTART;VALUE=DATE:20250928\nSUMMARY:上班\nCATEGORIES:WORKDAY\nEND:VEVENT\n",
+        )).unwrap();
+        let status = |value: &str| block_on(calendar.day_status(date(value))).unwrap();
+        assert_eq!(status("2025-10-01"), DayStatus::Holiday);
+        assert_eq!(status("2025-10-02"), DayStatus::Holiday);
+        assert_eq!(status("2025-10-03"), DayStatus::Workday);
+        assert_eq!(status("2025-09-28"), DayStatus::MakeupWorkday);
+        assert_eq!(status("2025-10-04"), DayStatus::Weekend);
+    }
+}
@@ -1,15 +1,186 @@
 use worker::*;
 use serde::{Deserialize, Serialize};
-use chrono::{Utc, NaiveDate, Datelike};
+use chrono::{NaiveDate, Utc};
 use anyhow::{Result, anyhow};
+use wasm_bindgen::JsValue;
+
+use crate::ai_usage::{AIUsageRecord, MonthlyUsageSummary};
+use crate::backup::{self, Backup, BackupRow, BackupTable, ConflictMode, ImportSummary, BACKUP_FORMAT_VERSION, BACKUP_TABLES};
+use crate::calendar::WorkingCalendar;
+use crate::holiday_calendar::HolidayCalendarEntry;
+use crate::leave::{leave_days, LeaveRecord};
+use crate::migrations::{self, MigrationStep, MIGRATIONS};
+use crate::project_codes::ProjectCodeRule;
+use crate::report_history::StoredReport;
+use crate::standup_run::{DigestRun, RunStatus, StandupRun, SummaryRun, MAX_RUN_ATTEMPTS, STALE_RUNNING_MINUTES};
+use crate::task_store;
+use crate::team::TeamMember;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// 按任务键批量查询时每条语句绑定的任务键数量，低于 D1 的 100 个绑定参数上限
+const KEYS_PER_QUERY: usize = 90;
+
+/// taiga_tasks 和 archived_tasks 表中与 TaigaTaskRecord 对应的列
+pub const TASK_COLUMNS: &str = "task_key, first_seen_date, last_seen_date, total_days, status, extra_days, status_source";
 
 /// Taiga 任务记录
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TaigaTaskRecord {
     pub task_key: String,        // 格式：project-name#task_id
     pub first_seen_date: String, // 首次出现日期 YYYY-MM-DD
     pub last_seen_date: String,  // 最后出现日期 YYYY-MM-DD
     pub total_days: i32,         // 累积工作天数
+    #[serde(default)]
+    pub status: TaskStatus,      // 任务状态，完成后天数不再增加
+    #[serde(default)]
+    pub extra_days: i32,         // 手动调整的天数，计算天数时加上
+    #[serde(default)]
+    pub status_source: TaskStatusSource, // 状态的来源，手动标记的完成不会被自动重新打开
+}
+
+impl TaigaTaskRecord {
+    /// 今天首次出现的任务
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn new(task_key: &str, today: &str) -> Self {
+        Self {
+            task_key: task_key.to_string(),
+            first_seen_date: today.to_string(),
+            last_seen_date: today.to_string(),
+            total_days: 1,
+            status: TaskStatus::Open,
+            extra_days: 0,
+            status_source: TaskStatusSource::Auto,
+        }
+    }
+}
+
+/// 管理接口对任务的手动调整，未填写的字段保持不变
+#[derive(Debug, Clone, Deserialize)]
+pub struct TaskUpdate {
+    pub task_key: String,
+    #[serde(default)]
+    pub first_seen_date: Option<String>, // 任务实际开始日期 YYYY-MM-DD
+    #[serde(default)]
+    pub total_days: Option<i32>,         // 当前应显示的天数，之后在此基础上继续累积
+    #[serde(default)]
+    pub status: Option<TaskStatus>,
+}
+
+impl TaskUpdate {
+    pub fn validate(&self) -> Result<()> {
+        if self.task_key.trim().is_empty() {
+            return Err(anyhow!("task_key 不能为空"));
+        }
+        if let Some(date) = &self.first_seen_date {
+            NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map_err(|_| anyhow!("first_seen_date 格式无效: {}，请使用 YYYY-MM-DD 格式", date))?;
+        }
+        if let Some(days) = self.total_days {
+            if days < 1 {
+                return Err(anyhow!("total_days 不能小于 1"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 任务状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    #[default]
+    Open,
+    Done,
+}
+
+impl TaskStatus {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "open" => Some(TaskStatus::Open),
+            "done" => Some(TaskStatus::Done),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskStatus::Open => "open",
+            TaskStatus::Done => "done",
+        }
+    }
+}
+
+/// 任务状态的来源：auto 由 PR 合并情况自动设置，manual 由管理接口手动设置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatusSource {
+    #[default]
+    Auto,
+    Manual,
+}
+
+impl TaskStatusSource {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "auto" => Some(TaskStatusSource::Auto),
+            "manual" => Some(TaskStatusSource::Manual),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskStatusSource::Auto => "auto",
+            TaskStatusSource::Manual => "manual",
+        }
+    }
+}
+
+/// PR 与任务的关联
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrTaskLink {
+    pub repo: String,              // owner/name
+    pub pr_number: u32,
+    pub task_key: String,
+    pub first_linked_date: String, // 首次关联日期 YYYY-MM-DD
+    #[serde(default)]
+    pub merged_at: Option<String>, // 合并时间，未合并时为空
+}
+
+/// 任务记录及关联的 PR
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskHistory {
+    #[serde(flatten)]
+    pub task: TaigaTaskRecord,
+    pub prs: Vec<PrTaskLink>,
+}
+
+/// 任务天数的计算方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TaskDaysMode {
+    /// 首次出现到今天之间的工作日数
+    Span,
+    /// 任务实际出现在报告中的天数
+    #[default]
+    Active,
+}
+
+impl TaskDaysMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "span" => Some(TaskDaysMode::Span),
+            "active" => Some(TaskDaysMode::Active),
+            _ => None,
+        }
+    }
+}
+
+/// 累积任务天数时使用的成员信息
+pub struct TaskDayContext<'c> {
+    pub login: &'c str,                     // 报告所属成员，其请假日期不计入天数
+    pub calendar: &'c dyn WorkingCalendar,  // 成员的工作日日历（节假日、调休补班）
+    pub mode: TaskDaysMode,                 // 天数计算方式
+    pub today: NaiveDate,                   // 成员所在时区的今日日期
 }
 
 /// 数据库操作客户端
@@ -23,26 +194,68 @@ impl<'a> DatabaseClient<'a> {
         Self { db }
     }
 
-    /// 初始化数据库表
-    pub async fn init_tables(&self) -> Result<()> {
+    /// 执行尚未应用的数据库迁移，每个 Worker 实例只检查一次
+    /// 每个迁移和它在 schema_migrations 中的记录在同一个 batch 中执行，失败时整体回滚
+    pub async fn migrate(&self) -> Result<()> {
+        if migrations::migrations_checked() {
+            return Ok(());
+        }
+
         let create_table_sql = r#"
-            CREATE TABLE IF NOT EXISTS taiga_tasks (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                task_key TEXT UNIQUE NOT NULL,
-                first_seen_date TEXT NOT NULL,
-                last_seen_date TEXT NOT NULL,
-                total_days INTEGER NOT NULL DEFAULT 1,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            CREATE TABLE IF NOT EXISTS schema_migrations (
+                version INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                applied_at DATETIME DEFAULT CURRENT_TIMESTAMP
             )
         "#;
 
         self.db.prepare(create_table_sql).run().await
-            .map_err(|e| anyhow!("创建表失败: {:?}", e))?;
+            .map_err(|e| anyhow!("创建迁移记录表失败: {:?}", e))?;
+
+        let current_version = self.db.prepare("SELECT COALESCE(MAX(version), 0) AS version FROM schema_migrations")
+            .first::<i32>(Some("version")).await
+            .map_err(|e| anyhow!("查询数据库版本失败: {:?}", e))?
+            .unwrap_or(0);
+
+        for migration in MIGRATIONS.iter().filter(|migration| migration.version > current_version) {
+            let mut statements = Vec::new();
+            for step in migration.steps {
+                match step {
+                    MigrationStep::Sql(sql) => statements.push(self.db.prepare(*sql)),
+                    MigrationStep::AddColumn { table, column, definition } => {
+                        if !self.has_column(table, column).await? {
+                            statements.push(self.db.prepare(format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition)));
+                        }
+                    }
+                }
+            }
+            statements.push(
+                self.db.prepare("INSERT INTO schema_migrations (version, name) VALUES (?1, ?2)")
+                    .bind(&[migration.version.into(), migration.name.into()])?
+            );
+
+            self.db.batch(statements).await
+                .map_err(|e| anyhow!("执行数据库迁移 {} ({}) 失败: {:?}", migration.version, migration.name, e))?;
+
+            console_log!("✓ 已执行数据库迁移 {} ({})", migration.version, migration.name);
+        }
 
+        migrations::mark_migrations_checked();
         Ok(())
     }
 
+    /// 表中是否已有某列
+    async fn has_column(&self, table: &str, column: &str) -> Result<bool> {
+        let result = self.db.prepare(format!("PRAGMA table_info({})", table))
+            .all().await
+            .map_err(|e| anyhow!("查询 {} 表结构失败: {:?}", table, e))?;
+
+        let columns = result.results::<serde_json::Value>()
+            .map_err(|e| anyhow!("解析 {} 表结构失败: {:?}", table, e))?;
+
+        Ok(columns.iter().any(|row| row.get("name").and_then(|name| name.as_str()) == Some(column)))
+    }
+
     /// 从 Taiga URL 中提取任务键
     /// 例如: https://tree.taiga.io/project/zenai-international-soraka/task/41 
     /// 提取为: zenai-international-soraka#41
@@ -64,135 +277,1375 @@ impl<'a> DatabaseClient<'a> {
         None
     }
 
-    /// 记录或更新 Taiga 任务
-    pub async fn record_taiga_task(&self, task_key: &str) -> Result<i32> {
-        let today = Utc::now().format("%Y-%m-%d").to_string();
-        
-        // 首先尝试获取现有记录
-        if let Ok(existing_record) = self.get_task_record(task_key).await {
-            // 如果任务已存在，更新最后出现日期并计算天数
-            let total_days = self.calculate_work_days(&existing_record.first_seen_date, &today)?;
-            
-            let update_sql = r#"
-                UPDATE taiga_tasks 
-                SET last_seen_date = ?1, total_days = ?2, updated_at = CURRENT_TIMESTAMP
-                WHERE task_key = ?3
-            "#;
-            
-            self.db.prepare(update_sql)
-                .bind(&[today.into(), total_days.into(), task_key.into()])?
-                .run().await
-                .map_err(|e| anyhow!("更新任务记录失败: {:?}", e))?;
-            
-            Ok(total_days)
-        } else {
-            // 如果任务不存在，创建新记录
-            let insert_sql = r#"
-                INSERT INTO taiga_tasks (task_key, first_seen_date, last_seen_date, total_days)
-                VALUES (?1, ?2, ?3, 1)
-            "#;
-            
-            self.db.prepare(insert_sql)
-                .bind(&[task_key.into(), today.clone().into(), today.into()])?
-                .run().await
-                .map_err(|e| anyhow!("插入任务记录失败: {:?}", e))?;
-            
-            Ok(1)
+    /// 获取任务记录
+    pub async fn get_task_record(&self, task_key: &str) -> Result<TaigaTaskRecord> {
+        self.find_task_record(task_key).await?
+            .ok_or_else(|| anyhow!("未找到任务记录"))
+    }
+
+    /// 查询任务记录，不存在时返回 None
+    pub async fn find_task_record(&self, task_key: &str) -> Result<Option<TaigaTaskRecord>> {
+        let select_sql = format!("SELECT {} FROM taiga_tasks WHERE task_key = ?1", TASK_COLUMNS);
+
+        self.db.prepare(&select_sql)
+            .bind(&[task_key.into()])?
+            .first::<TaigaTaskRecord>(None).await
+            .map_err(|e| anyhow!("查询任务记录失败: {:?}", e))
+    }
+
+    /// 新建或更新任务记录
+    pub async fn save_task_record(&self, record: &TaigaTaskRecord) -> Result<()> {
+        let upsert_sql = format!(r#"
+            INSERT INTO taiga_tasks ({})
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            ON CONFLICT(task_key) DO UPDATE SET
+                first_seen_date = excluded.first_seen_date,
+                last_seen_date = excluded.last_seen_date,
+                total_days = excluded.total_days,
+                status = excluded.status,
+                extra_days = excluded.extra_days,
+                status_source = excluded.status_source,
+                updated_at = CURRENT_TIMESTAMP
+        "#, TASK_COLUMNS);
+
+        self.db.prepare(&upsert_sql)
+            .bind(&[
+                record.task_key.as_str().into(),
+                record.first_seen_date.as_str().into(),
+                record.last_seen_date.as_str().into(),
+                record.total_days.into(),
+                record.status.as_str().into(),
+                record.extra_days.into(),
+                record.status_source.as_str().into(),
+            ])?
+            .run().await
+            .map_err(|e| anyhow!("保存任务记录失败: {:?}", e))?;
+
+        Ok(())
+    }
+
+    /// 记录任务的活跃日期，已存在的日期忽略
+    pub async fn record_task_activity(&self, task_key: &str, dates: &[&str]) -> Result<()> {
+        let insert_sql = r#"
+            INSERT OR IGNORE INTO task_activity (task_key, activity_date)
+            VALUES (?1, ?2)
+        "#;
+
+        let mut statements = Vec::new();
+        for date in dates {
+            statements.push(
+                self.db.prepare(insert_sql)
+                    .bind(&[task_key.into(), (*date).into()])?,
+            );
         }
+
+        self.db.batch(statements).await
+            .map_err(|e| anyhow!("记录任务活跃日期失败: {:?}", e))?;
+
+        Ok(())
     }
 
-    /// 获取任务记录
-    pub async fn get_task_record(&self, task_key: &str) -> Result<TaigaTaskRecord> {
+    /// 统计任务的活跃天数
+    pub async fn count_active_days(&self, task_key: &str) -> Result<i32> {
         let select_sql = r#"
-            SELECT task_key, first_seen_date, last_seen_date, total_days
-            FROM taiga_tasks 
+            SELECT COUNT(DISTINCT activity_date) AS count
+            FROM task_activity
             WHERE task_key = ?1
         "#;
 
-        let result = self.db.prepare(select_sql)
+        let count = self.db.prepare(select_sql)
             .bind(&[task_key.into()])?
-            .first::<TaigaTaskRecord>(None).await
-            .map_err(|e| anyhow!("查询任务记录失败: {:?}", e))?;
+            .first::<i32>(Some("count")).await
+            .map_err(|e| anyhow!("统计任务活跃天数失败: {:?}", e))?;
+
+        Ok(count.unwrap_or(0))
+    }
+
+    /// 批量获取任务记录，不存在的任务忽略
+    pub async fn get_task_records(&self, task_keys: &[String]) -> Result<Vec<TaigaTaskRecord>> {
+        let select_sql = format!("SELECT {} FROM taiga_tasks WHERE task_key IN", TASK_COLUMNS);
+        self.select_by_keys(&select_sql, task_keys).await
+            .map_err(|e| anyhow!("查询任务记录失败: {}", e))
+    }
+
+    /// 按任务键（包含 query）和状态查询任务，最近出现的在前
+    pub async fn list_task_records(&self, query: Option<&str>, status: Option<TaskStatus>, limit: i32) -> Result<Vec<TaigaTaskRecord>> {
+        let select_sql = format!(r#"
+            SELECT {}
+            FROM taiga_tasks
+            WHERE (?1 IS NULL OR instr(task_key, ?1) > 0)
+              AND (?2 IS NULL OR status = ?2)
+            ORDER BY last_seen_date DESC, task_key
+            LIMIT ?3
+        "#, TASK_COLUMNS);
+
+        let query = match query {
+            Some(query) => query.into(),
+            None => JsValue::NULL,
+        };
+        let status = match status {
+            Some(status) => status.as_str().into(),
+            None => JsValue::NULL,
+        };
+
+        let result = self.db.prepare(&select_sql)
+            .bind(&[query, status, limit.into()])?
+            .all().await
+            .map_err(|e| anyhow!("查询任务列表失败: {:?}", e))?;
 
-        result.ok_or_else(|| anyhow!("未找到任务记录"))
+        result.results::<TaigaTaskRecord>()
+            .map_err(|e| anyhow!("解析任务列表失败: {:?}", e))
     }
 
-    /// 获取任务的工作天数
-    pub async fn get_task_days(&self, task_key: &str) -> Result<i32> {
-        match self.get_task_record(task_key).await {
-            Ok(record) => Ok(record.total_days),
-            Err(_) => Ok(1), // 如果没有记录，默认为第一天
+    /// 查询任务列表并附带每个任务关联的 PR，筛选条件与 list_task_records 相同
+    pub async fn list_task_history(&self, query: Option<&str>, status: Option<TaskStatus>, limit: i32) -> Result<Vec<TaskHistory>> {
+        let tasks = self.list_task_records(query, status, limit).await?;
+        if tasks.is_empty() {
+            return Ok(Vec::new());
         }
+
+        // 用同样的筛选条件作为子查询，避免任务较多时超出绑定参数数量限制
+        let links_sql = r#"
+            SELECT repo, pr_number, task_key, first_linked_date, merged_at
+            FROM pr_task_links
+            WHERE task_key IN (
+                SELECT task_key
+                FROM taiga_tasks
+                WHERE (?1 IS NULL OR instr(task_key, ?1) > 0)
+                  AND (?2 IS NULL OR status = ?2)
+                ORDER BY last_seen_date DESC, task_key
+                LIMIT ?3
+            )
+            ORDER BY first_linked_date, repo, pr_number
+        "#;
+
+        let query = match query {
+            Some(query) => query.into(),
+            None => JsValue::NULL,
+        };
+        let status = match status {
+            Some(status) => status.as_str().into(),
+            None => JsValue::NULL,
+        };
+
+        let result = self.db.prepare(links_sql)
+            .bind(&[query, status, limit.into()])?
+            .all().await
+            .map_err(|e| anyhow!("查询任务关联的 PR 失败: {:?}", e))?;
+        let links = result.results::<PrTaskLink>()
+            .map_err(|e| anyhow!("解析任务关联的 PR 失败: {:?}", e))?;
+
+        let mut links_by_task: HashMap<String, Vec<PrTaskLink>> = HashMap::new();
+        for link in links {
+            links_by_task.entry(link.task_key.clone()).or_default().push(link);
+        }
+
+        Ok(tasks.into_iter()
+            .map(|task| {
+                let prs = links_by_task.remove(&task.task_key).unwrap_or_default();
+                TaskHistory { task, prs }
+            })
+            .collect())
+    }
+
+    /// 查询引用这些任务的全部 PR
+    pub async fn list_pr_task_links(&self, task_keys: &[String]) -> Result<Vec<PrTaskLink>> {
+        let select_sql = "SELECT repo, pr_number, task_key, first_linked_date, merged_at FROM pr_task_links WHERE task_key IN";
+        self.select_by_keys(select_sql, task_keys).await
+            .map_err(|e| anyhow!("查询任务关联的 PR 失败: {}", e))
     }
 
-    /// 计算工作天数（排除周末）
-    fn calculate_work_days(&self, start_date: &str, end_date: &str) -> Result<i32> {
-        let start = NaiveDate::parse_from_str(start_date, "%Y-%m-%d")
-            .map_err(|e| anyhow!("起始日期格式错误: {}", e))?;
-        let end = NaiveDate::parse_from_str(end_date, "%Y-%m-%d")
-            .map_err(|e| anyhow!("结束日期格式错误: {}", e))?;
+    /// 记录 PR 的合并时间
+    pub async fn set_pr_merged_at(&self, repo: &str, pr_number: u32, merged_at: &str) -> Result<()> {
+        self.db.prepare("UPDATE pr_task_links SET merged_at = ?1 WHERE repo = ?2 AND pr_number = ?3")
+            .bind(&[merged_at.into(), repo.into(), pr_number.into()])?
+            .run().await
+            .map_err(|e| anyhow!("记录 PR 合并时间失败: {:?}", e))?;
 
-        if end < start {
-            return Ok(1);
+        Ok(())
+    }
+
+    /// 记录 PR 与任务的关联，已存在的关联保留首次关联日期，并更新合并时间
+    pub async fn record_pr_task_links(&self, links: &[PrTaskLink]) -> Result<()> {
+        if links.is_empty() {
+            return Ok(());
         }
 
-        let mut current = start;
-        let mut work_days = 0;
+        let insert_sql = r#"
+            INSERT INTO pr_task_links (repo, pr_number, task_key, first_linked_date, merged_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT(repo, pr_number, task_key) DO UPDATE SET
+                merged_at = COALESCE(excluded.merged_at, pr_task_links.merged_at)
+        "#;
 
-        while current <= end {
-            // 检查是否为工作日（周一到周五）
-            let weekday = current.weekday();
-            if weekday.number_from_monday() <= 5 {
-                work_days += 1;
-            }
-            
-            // 防止无限循环
-            if current == end {
-                break;
+        let mut statements = Vec::new();
+        for link in links {
+            statements.push(
+                self.db.prepare(insert_sql)
+                    .bind(&[
+                        link.repo.as_str().into(),
+                        link.pr_number.into(),
+                        link.task_key.as_str().into(),
+                        link.first_linked_date.as_str().into(),
+                        link.merged_at.as_deref().map(JsValue::from).unwrap_or(JsValue::NULL),
+                    ])?,
+            );
+        }
+
+        self.db.batch(statements).await
+            .map_err(|e| anyhow!("记录 PR 与任务的关联失败: {:?}", e))?;
+
+        Ok(())
+    }
+
+    /// 手动调整任务的开始日期、天数和状态
+    /// 修改天数时把差值记在 extra_days 中，之后出现时在调整后的天数上继续累积
+    pub async fn update_task_record(&self, update: &TaskUpdate, mode: TaskDaysMode) -> Result<TaigaTaskRecord> {
+        let mut record = self.get_task_record(&update.task_key).await?;
+
+        if let Some(first_seen_date) = &update.first_seen_date {
+            if first_seen_date.as_str() > record.last_seen_date.as_str() {
+                return Err(anyhow!("first_seen_date 不能晚于最后出现日期 {}", record.last_seen_date));
             }
-            
-            // 使用 chrono::Duration 来增加天数
-            current = current + chrono::Duration::days(1);
+            record.first_seen_date = first_seen_date.clone();
+            self.record_task_activity(&record.task_key, &[first_seen_date]).await?;
+        }
+
+        if let Some(total_days) = update.total_days {
+            let counted_days = match mode {
+                TaskDaysMode::Active => self.count_active_days(&record.task_key).await?,
+                TaskDaysMode::Span => record.total_days - record.extra_days,
+            };
+            record.extra_days = total_days - counted_days;
+            record.total_days = total_days;
+        }
+
+        // 手动标记完成的任务不会因为 PR 未全部合并而被自动重新打开；手动重新打开后恢复自动判断
+        if let Some(status) = update.status {
+            record.status = status;
+            record.status_source = match status {
+                TaskStatus::Done => TaskStatusSource::Manual,
+                TaskStatus::Open => TaskStatusSource::Auto,
+            };
         }
 
-        Ok(work_days.max(1)) // 至少返回1天
+        self.save_task_record(&record).await?;
+        Ok(record)
     }
 
-    /// 批量处理 Taiga URLs
-    pub async fn process_taiga_urls(&self, urls: Vec<&str>) -> Result<Vec<(String, i32)>> {
-        let mut results = Vec::new();
-        
-        for url in urls {
-            if let Some(task_key) = Self::extract_task_key_from_url(url) {
-                match self.record_taiga_task(&task_key).await {
-                    Ok(days) => results.push((task_key, days)),
-                    Err(e) => {
-                        console_log!("处理 Taiga 任务 {} 失败: {}", task_key, e);
-                        results.push((task_key, 1)); // 失败时默认为1天
-                    }
+    /// 把重复的任务 from 合并到 into：活跃日期合并，首次出现取较早、最后出现取较晚，天数取较大值
+    pub async fn merge_task_records(&self, from: &str, into: &str) -> Result<TaigaTaskRecord> {
+        if from == into {
+            return Err(anyhow!("不能把任务合并到自身"));
+        }
+        self.get_task_record(from).await
+            .map_err(|_| anyhow!("未找到任务记录: {}", from))?;
+        self.get_task_record(into).await
+            .map_err(|_| anyhow!("未找到任务记录: {}", into))?;
+
+        let merge_sql = r#"
+            UPDATE taiga_tasks
+            SET first_seen_date = MIN(first_seen_date, (SELECT first_seen_date FROM taiga_tasks WHERE task_key = ?1)),
+                last_seen_date = MAX(last_seen_date, (SELECT last_seen_date FROM taiga_tasks WHERE task_key = ?1)),
+                total_days = MAX(total_days, (SELECT total_days FROM taiga_tasks WHERE task_key = ?1)),
+                extra_days = MAX(extra_days, (SELECT extra_days FROM taiga_tasks WHERE task_key = ?1)),
+                updated_at = CURRENT_TIMESTAMP
+            WHERE task_key = ?2
+        "#;
+
+        let statements = vec![
+            self.db.prepare("INSERT OR IGNORE INTO task_activity (task_key, activity_date) SELECT ?2, activity_date FROM task_activity WHERE task_key = ?1")
+                .bind(&[from.into(), into.into()])?,
+            self.db.prepare("DELETE FROM task_activity WHERE task_key = ?1")
+                .bind(&[from.into()])?,
+            self.db.prepare("INSERT OR IGNORE INTO pr_task_links (repo, pr_number, task_key, first_linked_date, merged_at) SELECT repo, pr_number, ?2, first_linked_date, merged_at FROM pr_task_links WHERE task_key = ?1")
+                .bind(&[from.into(), into.into()])?,
+            self.db.prepare("DELETE FROM pr_task_links WHERE task_key = ?1")
+                .bind(&[from.into()])?,
+            self.db.prepare(merge_sql)
+                .bind(&[from.into(), into.into()])?,
+            self.db.prepare("DELETE FROM taiga_tasks WHERE task_key = ?1")
+                .bind(&[from.into()])?,
+        ];
+
+        self.db.batch(statements).await
+            .map_err(|e| anyhow!("合并任务 {} 到 {} 失败: {:?}", from, into, e))?;
+
+        self.get_task_record(into).await
+    }
+
+    /// 删除任务及其活跃记录、PR 关联和归档
+    pub async fn delete_task_record(&self, task_key: &str) -> Result<()> {
+        let statements = vec![
+            self.db.prepare("DELETE FROM task_activity WHERE task_key = ?1")
+                .bind(&[task_key.into()])?,
+            self.db.prepare("DELETE FROM pr_task_links WHERE task_key = ?1")
+                .bind(&[task_key.into()])?,
+            self.db.prepare("DELETE FROM archived_tasks WHERE task_key = ?1")
+                .bind(&[task_key.into()])?,
+            self.db.prepare("DELETE FROM taiga_tasks WHERE task_key = ?1")
+                .bind(&[task_key.into()])?,
+        ];
+
+        self.db.batch(statements).await
+            .map_err(|e| anyhow!("删除任务 {} 失败: {:?}", task_key, e))?;
+
+        Ok(())
+    }
+
+    /// 批量处理今天出现的任务，在一个 D1 batch 中记录活跃日期并更新天数，返回更新后的任务记录
+    /// 计算规则与 task_store::record_task 一致：已归档的任务先恢复，Span 按首次出现日期计算工作日，
+    /// 手动调整的 extra_days 加在计算结果上，已完成的任务只更新最后出现日期、天数不再增加；
+    /// completed_keys 中的任务在记入当天后标记为已完成，不在其中的自动完成任务重新打开。batch 在同一事务中执行，失败时整体回滚并返回错误
+    pub async fn process_task_keys(&self, task_keys: &[String], completed_keys: &[String], context: &TaskDayContext<'_>) -> Result<Vec<TaigaTaskRecord>> {
+        let mut unique_keys: Vec<String> = Vec::new();
+        for task_key in task_keys {
+            if !unique_keys.contains(task_key) {
+                unique_keys.push(task_key.clone());
+            }
+        }
+        let task_keys = unique_keys;
+
+        if task_keys.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let today = context.today.format("%Y-%m-%d").to_string();
+
+        // 按工作日计算时需要日历和请假记录，先读取已有（包括已归档）任务的首次出现日期，在批量写入前算好天数
+        let mut span_days = HashMap::new();
+        if context.mode == TaskDaysMode::Span {
+            let mut records = self.get_task_records(&task_keys).await?;
+            for archived in self.get_archived_task_records(&task_keys).await? {
+                if !records.iter().any(|record| record.task_key == archived.task_key) {
+                    records.push(archived);
                 }
             }
+            for record in records {
+                let days = task_store::calculate_work_days(self, &record.first_seen_date, &today, context).await?;
+                span_days.insert(record.task_key, days);
+            }
+        }
+
+        // 首次和最后出现日期必定是活跃日，一并写入，兼容活跃记录表创建之前的任务
+        let backfill_activity_sql = r#"
+            INSERT OR IGNORE INTO task_activity (task_key, activity_date)
+            SELECT task_key, first_seen_date FROM taiga_tasks WHERE task_key = ?1
+            UNION
+            SELECT task_key, last_seen_date FROM taiga_tasks WHERE task_key = ?1
+        "#;
+
+        let insert_activity_sql = r#"
+            INSERT OR IGNORE INTO task_activity (task_key, activity_date)
+            VALUES (?1, ?2)
+        "#;
+
+        // 自动标记完成的任务不在 completed_keys 中（例如又关联了未合并的 PR）时重新打开，手动标记的完成保持不变
+        let reopen_sql = "UPDATE taiga_tasks SET status = 'open', updated_at = CURRENT_TIMESTAMP WHERE task_key = ?1 AND status = 'done' AND status_source = 'auto'";
+
+        // 最后出现日期总是更新；已完成的任务天数不再增加；手动调整的 extra_days 加在计算结果上
+        let upsert_sql = match context.mode {
+            TaskDaysMode::Active => r#"
+                INSERT INTO taiga_tasks (task_key, first_seen_date, last_seen_date, total_days)
+                VALUES (?1, ?2, ?2, 1)
+                ON CONFLICT(task_key) DO UPDATE SET
+                    last_seen_date = excluded.last_seen_date,
+                    total_days = CASE WHEN taiga_tasks.status = 'open' THEN MAX(1, (
+                        SELECT COUNT(DISTINCT activity_date) FROM task_activity WHERE task_key = excluded.task_key
+                    ) + taiga_tasks.extra_days) ELSE taiga_tasks.total_days END,
+                    updated_at = CURRENT_TIMESTAMP
+            "#,
+            TaskDaysMode::Span => r#"
+                INSERT INTO taiga_tasks (task_key, first_seen_date, last_seen_date, total_days)
+                VALUES (?1, ?2, ?2, 1)
+                ON CONFLICT(task_key) DO UPDATE SET
+                    last_seen_date = excluded.last_seen_date,
+                    total_days = CASE WHEN taiga_tasks.status = 'open' THEN MAX(1, ?3 + taiga_tasks.extra_days)
+                        ELSE taiga_tasks.total_days END,
+                    updated_at = CURRENT_TIMESTAMP
+            "#,
+        };
+
+        // 关联的 PR 全部合并的任务标记为已完成，当天仍计入天数
+        let complete_sql = "UPDATE taiga_tasks SET status = 'done', status_source = 'auto', updated_at = CURRENT_TIMESTAMP WHERE task_key = ?1 AND status = 'open'";
+
+        let mut statements = Vec::new();
+        for task_key in &task_keys {
+            // 已归档的任务重新出现时先恢复，保留原来的首次出现日期和天数
+            statements.extend(self.restore_archived_statements(task_key)?);
+            statements.push(self.db.prepare(backfill_activity_sql).bind(&[task_key.as_str().into()])?);
+            statements.push(
+                self.db.prepare(insert_activity_sql)
+                    .bind(&[task_key.as_str().into(), today.as_str().into()])?,
+            );
+            let completed = completed_keys.contains(task_key);
+            if !completed {
+                statements.push(self.db.prepare(reopen_sql).bind(&[task_key.as_str().into()])?);
+            }
+            let upsert = match context.mode {
+                TaskDaysMode::Active => self.db.prepare(upsert_sql)
+                    .bind(&[task_key.as_str().into(), today.as_str().into()])?,
+                TaskDaysMode::Span => self.db.prepare(upsert_sql)
+                    .bind(&[
+                        task_key.as_str().into(),
+                        today.as_str().into(),
+                        span_days.get(task_key).copied().unwrap_or(1).into(),
+                    ])?,
+            };
+            statements.push(upsert);
+            if completed {
+                statements.push(self.db.prepare(complete_sql).bind(&[task_key.as_str().into()])?);
+            }
+        }
+
+        // 最后读回写入后的任务记录，按 KEYS_PER_QUERY 分组避免超过绑定参数上限
+        let chunks = task_keys.chunks(KEYS_PER_QUERY);
+        let select_count = chunks.len();
+        for chunk in chunks {
+            let placeholders = (1..=chunk.len())
+                .map(|i| format!("?{}", i))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let params: Vec<JsValue> = chunk.iter().map(|key| key.as_str().into()).collect();
+            statements.push(
+                self.db.prepare(format!("SELECT {} FROM taiga_tasks WHERE task_key IN ({})", TASK_COLUMNS, placeholders))
+                    .bind(&params)?,
+            );
+        }
+
+        let results = self.db.batch(statements).await
+            .map_err(|e| anyhow!("批量更新任务天数失败: {:?}", e))?;
+
+        let mut records: HashMap<String, TaigaTaskRecord> = HashMap::new();
+        for result in results.iter().skip(results.len().saturating_sub(select_count)) {
+            let rows = result.results::<TaigaTaskRecord>()
+                .map_err(|e| anyhow!("解析任务记录失败: {:?}", e))?;
+            records.extend(rows.into_iter().map(|record| (record.task_key.clone(), record)));
         }
-        
-        Ok(results)
+
+        task_keys.into_iter()
+            .map(|task_key| records.remove(&task_key)
+                .ok_or_else(|| anyhow!("批量更新后未找到任务记录: {}", task_key)))
+            .collect()
     }
 
-    /// 清理旧的任务记录（超过30天未出现的任务）
-    pub async fn cleanup_old_tasks(&self) -> Result<()> {
-        let cleanup_date = (Utc::now().date_naive() - chrono::Duration::days(30))
+    /// 归档超过保留天数未出现的任务，返回归档数量
+    pub async fn archive_stale_tasks(&self, retention_days: i64, today: NaiveDate) -> Result<usize> {
+        let cutoff = (today - chrono::Duration::days(retention_days))
             .format("%Y-%m-%d")
             .to_string();
 
-        let delete_sql = r#"
-            DELETE FROM taiga_tasks 
-            WHERE last_seen_date < ?1
+        task_store::TaskStore::archive_tasks(self, &cutoff).await
+    }
+
+    /// 把最后出现日期早于 cutoff 的任务移到 archived_tasks，活跃记录保留以便恢复
+    pub async fn archive_tasks_before(&self, cutoff: &str) -> Result<usize> {
+        let count = self.db.prepare("SELECT COUNT(*) AS count FROM taiga_tasks WHERE last_seen_date < ?1")
+            .bind(&[cutoff.into()])?
+            .first::<i32>(Some("count")).await
+            .map_err(|e| anyhow!("统计待归档任务失败: {:?}", e))?
+            .unwrap_or(0);
+
+        if count == 0 {
+            return Ok(0);
+        }
+
+        let archive_sql = format!(
+            "INSERT OR REPLACE INTO archived_tasks ({0}) SELECT {0} FROM taiga_tasks WHERE last_seen_date < ?1",
+            TASK_COLUMNS
+        );
+
+        let statements = vec![
+            self.db.prepare(&archive_sql).bind(&[cutoff.into()])?,
+            self.db.prepare("DELETE FROM taiga_tasks WHERE last_seen_date < ?1")
+                .bind(&[cutoff.into()])?,
+        ];
+
+        self.db.batch(statements).await
+            .map_err(|e| anyhow!("归档旧任务记录失败: {:?}", e))?;
+
+        Ok(count as usize)
+    }
+
+    /// 恢复已归档的任务，任务不在归档中时返回 None
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub async fn restore_archived_task(&self, task_key: &str) -> Result<Option<TaigaTaskRecord>> {
+        if self.get_archived_task_records(&[task_key.to_string()]).await?.is_empty() {
+            return Ok(None);
+        }
+
+        let statements = self.restore_archived_statements(task_key)?;
+        self.db.batch(statements).await
+            .map_err(|e| anyhow!("恢复归档任务失败: {:?}", e))?;
+
+        self.find_task_record(task_key).await
+    }
+
+    /// 把归档中的任务移回 taiga_tasks 的语句，任务已在 taiga_tasks 中时只删除归档
+    fn restore_archived_statements(&self, task_key: &str) -> Result<Vec<D1PreparedStatement>> {
+        let restore_sql = format!(
+            "INSERT OR IGNORE INTO taiga_tasks ({0}) SELECT {0} FROM archived_tasks WHERE task_key = ?1",
+            TASK_COLUMNS
+        );
+
+        Ok(vec![
+            self.db.prepare(&restore_sql).bind(&[task_key.into()])?,
+            self.db.prepare("DELETE FROM archived_tasks WHERE task_key = ?1")
+                .bind(&[task_key.into()])?,
+        ])
+    }
+
+    /// 批量获取已归档的任务记录
+    pub async fn get_archived_task_records(&self, task_keys: &[String]) -> Result<Vec<TaigaTaskRecord>> {
+        let select_sql = format!("SELECT {} FROM archived_tasks WHERE task_key IN", TASK_COLUMNS);
+        self.select_by_keys(&select_sql, task_keys).await
+            .map_err(|e| anyhow!("查询归档任务失败: {}", e))
+    }
+
+    /// 按任务键批量查询，select_sql 以 `task_key IN` 结尾
+    /// D1 单条语句最多绑定 100 个参数，任务键按 KEYS_PER_QUERY 分组，在一个 batch 中查询
+    async fn select_by_keys<T: serde::de::DeserializeOwned>(&self, select_sql: &str, task_keys: &[String]) -> Result<Vec<T>> {
+        if task_keys.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut statements = Vec::new();
+        for chunk in task_keys.chunks(KEYS_PER_QUERY) {
+            let placeholders = (1..=chunk.len())
+                .map(|i| format!("?{}", i))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let params: Vec<JsValue> = chunk.iter().map(|key| key.as_str().into()).collect();
+            statements.push(self.db.prepare(format!("{} ({})", select_sql, placeholders)).bind(&params)?);
+        }
+
+        let results = self.db.batch(statements).await
+            .map_err(|e| anyhow!("{:?}", e))?;
+        let mut rows = Vec::new();
+        for result in results {
+            rows.extend(result.results::<T>().map_err(|e| anyhow!("解析查询结果失败: {:?}", e))?);
+        }
+        Ok(rows)
+    }
+
+    /// 记录一次 AI 调用的用量和成本
+    pub async fn record_ai_usage(&self, record: &AIUsageRecord) -> Result<()> {
+        let insert_sql = r#"
+            INSERT INTO ai_usage (user_login, run_date, model, prompt_tokens, completion_tokens,
+                                  total_tokens, latency_ms, estimated_cost, currency)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+        "#;
+
+        self.db.prepare(insert_sql)
+            .bind(&[
+                record.user_login.as_str().into(),
+                record.run_date.as_str().into(),
+                record.model.as_str().into(),
+                record.prompt_tokens.into(),
+                record.completion_tokens.into(),
+                record.total_tokens.into(),
+                record.latency_ms.into(),
+                record.estimated_cost.into(),
+                record.currency.as_str().into(),
+            ])?
+            .run().await
+            .map_err(|e| anyhow!("记录 AI 用量失败: {:?}", e))?;
+
+        Ok(())
+    }
+
+    /// 按用户汇总指定月份（YYYY-MM）的 AI 用量
+    pub async fn get_monthly_ai_usage(&self, month: &str) -> Result<Vec<MonthlyUsageSummary>> {
+        let select_sql = r#"
+            SELECT user_login,
+                   COUNT(*) AS runs,
+                   SUM(prompt_tokens) AS prompt_tokens,
+                   SUM(completion_tokens) AS completion_tokens,
+                   SUM(total_tokens) AS total_tokens,
+                   AVG(latency_ms) AS avg_latency_ms,
+                   SUM(estimated_cost) AS total_cost,
+                   currency
+            FROM ai_usage
+            WHERE substr(run_date, 1, 7) = ?1
+            GROUP BY user_login, currency
+            ORDER BY total_cost DESC
+        "#;
+
+        let result = self.db.prepare(select_sql)
+            .bind(&[month.into()])?
+            .all().await
+            .map_err(|e| anyhow!("查询 AI 用量失败: {:?}", e))?;
+
+        result.results::<MonthlyUsageSummary>()
+            .map_err(|e| anyhow!("解析 AI 用量失败: {:?}", e))
+    }
+
+    /// 本地日历是否已有指定年份的数据
+    pub async fn has_holiday_year(&self, year: i32) -> Result<bool> {
+        let select_sql = r#"
+            SELECT year FROM holiday_calendar_years WHERE year = ?1
+        "#;
+
+        let result = self.db.prepare(select_sql)
+            .bind(&[year.into()])?
+            .first::<serde_json::Value>(None).await
+            .map_err(|e| anyhow!("查询日历年份失败: {:?}", e))?;
+
+        Ok(result.is_some())
+    }
+
+    /// 查询本地日历中指定日期的记录
+    pub async fn get_holiday_entry(&self, date: &str) -> Result<Option<HolidayCalendarEntry>> {
+        let select_sql = r#"
+            SELECT date, status, name
+            FROM holiday_calendar
+            WHERE date = ?1
+        "#;
+
+        self.db.prepare(select_sql)
+            .bind(&[date.into()])?
+            .first::<HolidayCalendarEntry>(None).await
+            .map_err(|e| anyhow!("查询日历记录失败: {:?}", e))
+    }
+
+    /// 查询指定年份的全部日历记录
+    pub async fn list_holiday_year(&self, year: i32) -> Result<Vec<HolidayCalendarEntry>> {
+        let select_sql = r#"
+            SELECT date, status, name
+            FROM holiday_calendar
+            WHERE year = ?1
+            ORDER BY date
+        "#;
+
+        let result = self.db.prepare(select_sql)
+            .bind(&[year.into()])?
+            .all().await
+            .map_err(|e| anyhow!("查询日历记录失败: {:?}", e))?;
+
+        result.results::<HolidayCalendarEntry>()
+            .map_err(|e| anyhow!("解析日历记录失败: {:?}", e))
+    }
+
+    /// 导入一整年的日历，覆盖该年已有的数据
+    pub async fn import_holiday_year(&self, year: i32, entries: &[HolidayCalendarEntry], source: &str) -> Result<usize> {
+        let mut statements = vec![
+            self.db.prepare("DELETE FROM holiday_calendar WHERE year = ?1")
+                .bind(&[year.into()])?,
+        ];
+
+        let insert_sql = r#"
+            INSERT INTO holiday_calendar (date, year, status, name, source)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+        "#;
+        for entry in entries {
+            let name = match &entry.name {
+                Some(name) => name.as_str().into(),
+                None => JsValue::NULL,
+            };
+            statements.push(
+                self.db.prepare(insert_sql)
+                    .bind(&[entry.date.as_str().into(), year.into(), entry.status.into(), name, source.into()])?,
+            );
+        }
+
+        let upsert_year_sql = r#"
+            INSERT INTO holiday_calendar_years (year, source, imported_at)
+            VALUES (?1, ?2, CURRENT_TIMESTAMP)
+            ON CONFLICT(year) DO UPDATE SET source = excluded.source, imported_at = CURRENT_TIMESTAMP
+        "#;
+        statements.push(
+            self.db.prepare(upsert_year_sql)
+                .bind(&[year.into(), source.into()])?,
+        );
+
+        // D1 的 batch 在同一事务中执行，任一语句失败都会整体回滚
+        self.db.batch(statements).await
+            .map_err(|e| anyhow!("导入 {} 年日历失败: {:?}", year, e))?;
+
+        Ok(entries.len())
+    }
+
+    /// 获取全部团队成员
+    pub async fn list_team_members(&self) -> Result<Vec<TeamMember>> {
+        let select_sql = r#"
+            SELECT login, display_name, calendar, timezone, send_time
+            FROM team_members
+            ORDER BY login
+        "#;
+
+        let result = self.db.prepare(select_sql)
+            .all().await
+            .map_err(|e| anyhow!("查询团队成员失败: {:?}", e))?;
+
+        result.results::<TeamMember>()
+            .map_err(|e| anyhow!("解析团队成员失败: {:?}", e))
+    }
+
+    /// 获取指定团队成员
+    pub async fn get_team_member(&self, login: &str) -> Result<Option<TeamMember>> {
+        let select_sql = r#"
+            SELECT login, display_name, calendar, timezone, send_time
+            FROM team_members
+            WHERE login = ?1
+        "#;
+
+        self.db.prepare(select_sql)
+            .bind(&[login.into()])?
+            .first::<TeamMember>(None).await
+            .map_err(|e| anyhow!("查询团队成员失败: {:?}", e))
+    }
+
+    /// 新增或更新团队成员
+    pub async fn upsert_team_member(&self, member: &TeamMember) -> Result<()> {
+        let upsert_sql = r#"
+            INSERT INTO team_members (login, display_name, calendar, timezone, send_time)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT(login) DO UPDATE SET
+                display_name = excluded.display_name,
+                calendar = excluded.calendar,
+                timezone = excluded.timezone,
+                send_time = excluded.send_time,
+                updated_at = CURRENT_TIMESTAMP
+        "#;
+
+        let display_name = match &member.display_name {
+            Some(name) => name.as_str().into(),
+            None => JsValue::NULL,
+        };
+
+        self.db.prepare(upsert_sql)
+            .bind(&[
+                member.login.as_str().into(),
+                display_name,
+                member.calendar.as_str().into(),
+                member.timezone.trim().into(),
+                member.send_time.trim().into(),
+            ])?
+            .run().await
+            .map_err(|e| anyhow!("保存团队成员失败: {:?}", e))?;
+
+        Ok(())
+    }
+
+    /// 删除团队成员
+    pub async fn delete_team_member(&self, login: &str) -> Result<()> {
+        self.db.prepare("DELETE FROM team_members WHERE login = ?1")
+            .bind(&[login.into()])?
+            .run().await
+            .map_err(|e| anyhow!("删除团队成员失败: {:?}", e))?;
+
+        Ok(())
+    }
+
+    /// 获取 ICS 日历内容
+    pub async fn get_ics_calendar(&self, name: &str) -> Result<Option<String>> {
+        self.db.prepare("SELECT content FROM ics_calendars WHERE name = ?1")
+            .bind(&[name.into()])?
+            .first::<String>(Some("content")).await
+            .map_err(|e| anyhow!("查询 ICS 日历失败: {:?}", e))
+    }
+
+    /// 获取全部 ICS 日历名称
+    pub async fn list_ics_calendars(&self) -> Result<Vec<String>> {
+        let result = self.db.prepare("SELECT name FROM ics_calendars ORDER BY name")
+            .all().await
+            .map_err(|e| anyhow!("查询 ICS 日历失败: {:?}", e))?;
+
+        let rows = result.results::<serde_json::Value>()
+            .map_err(|e| anyhow!("解析 ICS 日历失败: {:?}", e))?;
+
+        Ok(rows.into_iter()
+            .filter_map(|row| row.get("name").and_then(|name| name.as_str()).map(|name| name.to_string()))
+            .collect())
+    }
+
+    /// 保存 ICS 日历，同名日历会被覆盖
+    pub async fn save_ics_calendar(&self, name: &str, content: &str) -> Result<()> {
+        let upsert_sql = r#"
+            INSERT INTO ics_calendars (name, content, updated_at)
+            VALUES (?1, ?2, CURRENT_TIMESTAMP)
+            ON CONFLICT(name) DO UPDATE SET content = excluded.content, updated_at = CURRENT_TIMESTAMP
+        "#;
+
+        self.db.prepare(upsert_sql)
+            .bind(&[name.into(), content.into()])?
+            .run().await
+            .map_err(|e| anyhow!("保存 ICS 日历失败: {:?}", e))?;
+
+        Ok(())
+    }
+
+    /// 新增请假记录
+    pub async fn add_leave(&self, leave: &LeaveRecord) -> Result<()> {
+        self.leave_insert_statement(leave)?
+            .run().await
+            .map_err(|e| anyhow!("保存请假记录失败: {:?}", e))?;
+
+        Ok(())
+    }
+
+    /// 用 ICS 导入的请假记录替换该成员之前从 ICS 导入的记录，手动登记的记录保留
+    pub async fn replace_ics_leave(&self, login: &str, leaves: &[LeaveRecord]) -> Result<usize> {
+        let mut statements = vec![
+            self.db.prepare("DELETE FROM leave WHERE login = ?1 AND source = 'ics'")
+                .bind(&[login.into()])?,
+        ];
+        for leave in leaves {
+            statements.push(self.leave_insert_statement(leave)?);
+        }
+
+        self.db.batch(statements).await
+            .map_err(|e| anyhow!("导入 {} 的请假记录失败: {:?}", login, e))?;
+
+        Ok(leaves.len())
+    }
+
+    fn leave_insert_statement(&self, leave: &LeaveRecord) -> Result<D1PreparedStatement> {
+        let insert_sql = r#"
+            INSERT INTO leave (login, start_date, end_date, reason, source)
+            VALUES (?1, ?2, ?3, ?4, ?5)
         "#;
 
-        self.db.prepare(delete_sql)
-            .bind(&[cleanup_date.into()])?
+        let reason = match &leave.reason {
+            Some(reason) => reason.as_str().into(),
+            None => JsValue::NULL,
+        };
+
+        Ok(self.db.prepare(insert_sql)
+            .bind(&[
+                leave.login.as_str().into(),
+                leave.start_date.as_str().into(),
+                leave.end_date.as_str().into(),
+                reason,
+                leave.source.as_str().into(),
+            ])?)
+    }
+
+    /// 获取请假记录，指定 login 时只返回该成员的记录
+    pub async fn list_leave(&self, login: Option<&str>) -> Result<Vec<LeaveRecord>> {
+        let statement = match login {
+            Some(login) => self.db.prepare(r#"
+                    SELECT id, login, start_date, end_date, reason, source
+                    FROM leave
+                    WHERE login = ?1
+                    ORDER BY start_date
+                "#)
+                .bind(&[login.into()])?,
+            None => self.db.prepare(r#"
+                    SELECT id, login, start_date, end_date, reason, source
+                    FROM leave
+                    ORDER BY login, start_date
+                "#),
+        };
+
+        let result = statement.all().await
+            .map_err(|e| anyhow!("查询请假记录失败: {:?}", e))?;
+
+        result.results::<LeaveRecord>()
+            .map_err(|e| anyhow!("解析请假记录失败: {:?}", e))
+    }
+
+    /// 删除请假记录
+    pub async fn delete_leave(&self, id: i64) -> Result<()> {
+        self.db.prepare("DELETE FROM leave WHERE id = ?1")
+            .bind(&[(id as f64).into()])?
             .run().await
-            .map_err(|e| anyhow!("清理旧任务记录失败: {:?}", e))?;
+            .map_err(|e| anyhow!("删除请假记录失败: {:?}", e))?;
 
         Ok(())
     }
-} 
\ No newline at end of file
+
+    /// 检查成员在指定日期是否请假
+    pub async fn is_on_leave(&self, login: &str, date: &str) -> Result<bool> {
+        let select_sql = r#"
+            SELECT COUNT(*) AS count
+            FROM leave
+            WHERE login = ?1 AND start_date <= ?2 AND end_date >= ?2
+        "#;
+
+        let count = self.db.prepare(select_sql)
+            .bind(&[login.into(), date.into()])?
+            .first::<i32>(Some("count")).await
+            .map_err(|e| anyhow!("查询请假记录失败: {:?}", e))?;
+
+        Ok(count.unwrap_or(0) > 0)
+    }
+
+    /// 获取成员在日期范围内的全部请假日期
+    pub async fn get_leave_days(&self, login: &str, start_date: &str, end_date: &str) -> Result<HashSet<NaiveDate>> {
+        let select_sql = r#"
+            SELECT id, login, start_date, end_date, reason, source
+            FROM leave
+            WHERE login = ?1 AND start_date <= ?3 AND end_date >= ?2
+        "#;
+
+        let result = self.db.prepare(select_sql)
+            .bind(&[login.into(), start_date.into(), end_date.into()])?
+            .all().await
+            .map_err(|e| anyhow!("查询请假记录失败: {:?}", e))?;
+
+        let leaves = result.results::<LeaveRecord>()
+            .map_err(|e| anyhow!("解析请假记录失败: {:?}", e))?;
+
+        Ok(leave_days(&leaves))
+    }
+
+    /// 获取成员某一天的运行记录
+    pub async fn get_standup_run(&self, login: &str, run_date: &str) -> Result<Option<StandupRun>> {
+        let select_sql = r#"
+            SELECT login, run_date, status, report, channels, error, attempts, updated_at
+            FROM standup_runs
+            WHERE login = ?1 AND run_date = ?2
+        "#;
+
+        self.db.prepare(select_sql)
+            .bind(&[login.into(), run_date.into()])?
+            .first::<StandupRun>(None).await
+            .map_err(|e| anyhow!("查询站会运行记录失败: {:?}", e))
+    }
+
+    /// 抢占成员某一天的运行，尝试次数加一，返回是否抢占成功
+    /// 只有没有记录、失败或中断的运行可以抢占；force 时已成功的运行也可以重新运行，
+    /// 正在运行中的记录始终不能抢占，避免并发的定时任务和手动触发重复发送
+    pub async fn start_standup_run(&self, login: &str, run_date: &str, force: bool) -> Result<bool> {
+        let upsert_sql = format!(r#"
+            INSERT INTO standup_runs (login, run_date, status, attempts)
+            VALUES (?1, ?2, ?3, 1)
+            ON CONFLICT(login, run_date) DO UPDATE SET
+                status = excluded.status,
+                error = NULL,
+                attempts = standup_runs.attempts + 1,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE {}
+        "#, claimable_condition("standup_runs", force, None));
+
+        let result = self.db.prepare(&upsert_sql)
+            .bind(&[login.into(), run_date.into(), RunStatus::Running.as_str().into()])?
+            .run().await
+            .map_err(|e| anyhow!("记录站会运行开始失败: {:?}", e))?;
+
+        Ok(changed_rows(&result)? > 0)
+    }
+
+    /// 标记运行成功，记录报告内容和已发送的渠道
+    pub async fn complete_standup_run(&self, login: &str, run_date: &str, report: &str, channels: &[&str]) -> Result<()> {
+        let update_sql = r#"
+            UPDATE standup_runs
+            SET status = ?1, report = ?2, channels = ?3, error = NULL, updated_at = CURRENT_TIMESTAMP
+            WHERE login = ?4 AND run_date = ?5
+        "#;
+
+        let channels = serde_json::to_string(channels)?;
+        self.db.prepare(update_sql)
+            .bind(&[
+                RunStatus::Succeeded.as_str().into(),
+                report.into(),
+                channels.into(),
+                login.into(),
+                run_date.into(),
+            ])?
+            .run().await
+            .map_err(|e| anyhow!("记录站会运行结果失败: {:?}", e))?;
+
+        Ok(())
+    }
+
+    /// 标记运行失败，记录失败原因
+    pub async fn fail_standup_run(&self, login: &str, run_date: &str, error: &str) -> Result<()> {
+        let update_sql = r#"
+            UPDATE standup_runs
+            SET status = ?1, error = ?2, updated_at = CURRENT_TIMESTAMP
+            WHERE login = ?3 AND run_date = ?4
+        "#;
+
+        self.db.prepare(update_sql)
+            .bind(&[RunStatus::Failed.as_str().into(), error.into(), login.into(), run_date.into()])?
+            .run().await
+            .map_err(|e| anyhow!("记录站会运行失败原因失败: {:?}", e))?;
+
+        Ok(())
+    }
+
+    /// 获取成员某个周期的周报或月报运行记录
+    pub async fn get_summary_run(&self, login: &str, period: &str, period_end: &str) -> Result<Option<SummaryRun>> {
+        let select_sql = r#"
+            SELECT login, period, period_end, status, error, attempts, updated_at
+            FROM summary_runs
+            WHERE login = ?1 AND period = ?2 AND period_end = ?3
+        "#;
+
+        self.db.prepare(select_sql)
+            .bind(&[login.into(), period.into(), period_end.into()])?
+            .first::<SummaryRun>(None).await
+            .map_err(|e| anyhow!("查询汇总报告运行记录失败: {:?}", e))
+    }
+
+    /// 抢占周报或月报的运行，尝试次数加一，返回是否抢占成功
+    /// 只有没有记录、或失败和中断且尝试次数未达上限的运行可以抢占
+    pub async fn start_summary_run(&self, login: &str, period: &str, period_end: &str) -> Result<bool> {
+        let upsert_sql = format!(r#"
+            INSERT INTO summary_runs (login, period, period_end, status, attempts)
+            VALUES (?1, ?2, ?3, ?4, 1)
+            ON CONFLICT(login, period, period_end) DO UPDATE SET
+                status = excluded.status,
+                error = NULL,
+                attempts = summary_runs.attempts + 1,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE {}
+        "#, claimable_condition("summary_runs", false, Some(MAX_RUN_ATTEMPTS)));
+
+        let result = self.db.prepare(&upsert_sql)
+            .bind(&[login.into(), period.into(), period_end.into(), RunStatus::Running.as_str().into()])?
+            .run().await
+            .map_err(|e| anyhow!("记录汇总报告运行开始失败: {:?}", e))?;
+
+        Ok(changed_rows(&result)? > 0)
+    }
+
+    /// 记录周报或月报运行结果，失败时记录失败原因
+    pub async fn finish_summary_run(&self, login: &str, period: &str, period_end: &str, error: Option<&str>) -> Result<()> {
+        let update_sql = r#"
+            UPDATE summary_runs
+            SET status = ?1, error = ?2, updated_at = CURRENT_TIMESTAMP
+            WHERE login = ?3 AND period = ?4 AND period_end = ?5
+        "#;
+
+        let status = if error.is_some() { RunStatus::Failed } else { RunStatus::Succeeded };
+        self.db.prepare(update_sql)
+            .bind(&[
+                status.as_str().into(),
+                error.map(JsValue::from).unwrap_or(JsValue::NULL),
+                login.into(),
+                period.into(),
+                period_end.into(),
+            ])?
+            .run().await
+            .map_err(|e| anyhow!("记录汇总报告运行结果失败: {:?}", e))?;
+
+        Ok(())
+    }
+
+    /// 获取某一天的团队日报运行记录
+    pub async fn get_digest_run(&self, digest_date: &str) -> Result<Option<DigestRun>> {
+        let select_sql = r#"
+            SELECT digest_date, status, report, error, attempts, updated_at
+            FROM digest_runs
+            WHERE digest_date = ?1
+        "#;
+
+        self.db.prepare(select_sql)
+            .bind(&[digest_date.into()])?
+            .first::<DigestRun>(None).await
+            .map_err(|e| anyhow!("查询团队日报运行记录失败: {:?}", e))
+    }
+
+    /// 抢占某一天的团队日报运行，尝试次数加一，返回是否抢占成功
+    /// 定时任务只能抢占没有记录、或失败和中断且尝试次数未达上限的运行；
+    /// force 时已成功或已达上限的运行也可以重新运行，但正在运行中的记录不能抢占
+    pub async fn start_digest_run(&self, digest_date: &str, force: bool) -> Result<bool> {
+        let max_attempts = if force { None } else { Some(MAX_RUN_ATTEMPTS) };
+        let upsert_sql = format!(r#"
+            INSERT INTO digest_runs (digest_date, status, attempts)
+            VALUES (?1, ?2, 1)
+            ON CONFLICT(digest_date) DO UPDATE SET
+                status = excluded.status,
+                error = NULL,
+                attempts = digest_runs.attempts + 1,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE {}
+        "#, claimable_condition("digest_runs", force, max_attempts));
+
+        let result = self.db.prepare(&upsert_sql)
+            .bind(&[digest_date.into(), RunStatus::Running.as_str().into()])?
+            .run().await
+            .map_err(|e| anyhow!("记录团队日报运行开始失败: {:?}", e))?;
+
+        Ok(changed_rows(&result)? > 0)
+    }
+
+    /// 记录团队日报运行结果：成功时保存发送的内容，失败时记录失败原因
+    pub async fn finish_digest_run(&self, digest_date: &str, result: std::result::Result<&str, &str>) -> Result<()> {
+        let update_sql = r#"
+            UPDATE digest_runs
+            SET status = ?1, report = COALESCE(?2, report), error = ?3, updated_at = CURRENT_TIMESTAMP
+            WHERE digest_date = ?4
+        "#;
+
+        let (status, report, error) = match result {
+            Ok(report) => (RunStatus::Succeeded, JsValue::from(report), JsValue::NULL),
+            Err(error) => (RunStatus::Failed, JsValue::NULL, JsValue::from(error)),
+        };
+        self.db.prepare(update_sql)
+            .bind(&[status.as_str().into(), report, error, digest_date.into()])?
+            .run().await
+            .map_err(|e| anyhow!("记录团队日报运行结果失败: {:?}", e))?;
+
+        Ok(())
+    }
+
+    /// 保存已发送的站会报告
+    pub async fn save_standup_report(&self, report: &StoredReport) -> Result<()> {
+        let insert_sql = r#"
+            INSERT INTO standup_reports (login, report_date, report, raw_data, entries)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+        "#;
+
+        self.db.prepare(insert_sql)
+            .bind(&[
+                report.login.as_str().into(),
+                report.report_date.as_str().into(),
+                report.report.as_str().into(),
+                report.raw_data.as_str().into(),
+                report.entries.as_str().into(),
+            ])?
+            .run().await
+            .map_err(|e| anyhow!("保存站会报告失败: {:?}", e))?;
+
+        Ok(())
+    }
+
+    /// 查询日期范围内（包含首尾两天）的站会报告，指定 login 时只返回该成员的报告
+    pub async fn list_standup_reports(&self, login: Option<&str>, from: &str, to: &str) -> Result<Vec<StoredReport>> {
+        let statement = match login {
+            Some(login) => self.db.prepare(r#"
+                    SELECT id, login, report_date, report, raw_data, entries, created_at
+                    FROM standup_reports
+                    WHERE login = ?1 AND report_date >= ?2 AND report_date <= ?3
+                    ORDER BY report_date, created_at
+                "#)
+                .bind(&[login.into(), from.into(), to.into()])?,
+            None => self.db.prepare(r#"
+                    SELECT id, login, report_date, report, raw_data, entries, created_at
+                    FROM standup_reports
+                    WHERE report_date >= ?1 AND report_date <= ?2
+                    ORDER BY report_date, login, created_at
+                "#)
+                .bind(&[from.into(), to.into()])?,
+        };
+
+        let result = statement.all().await
+            .map_err(|e| anyhow!("查询站会报告失败: {:?}", e))?;
+
+        result.results::<StoredReport>()
+            .map_err(|e| anyhow!("解析站会报告失败: {:?}", e))
+    }
+
+    /// 查询项目代号规则，regex 规则按执行顺序排列
+    pub async fn list_project_code_rules(&self) -> Result<Vec<ProjectCodeRule>> {
+        let result = self.db.prepare(r#"
+                SELECT id, kind, pattern, replacement, position
+                FROM project_code_rules
+                ORDER BY kind, position, id
+            "#)
+            .all().await
+            .map_err(|e| anyhow!("查询项目代号规则失败: {:?}", e))?;
+
+        result.results::<ProjectCodeRule>()
+            .map_err(|e| anyhow!("解析项目代号规则失败: {:?}", e))
+    }
+
+    /// 添加或更新项目代号规则，同类型同 pattern 的规则会被覆盖
+    pub async fn save_project_code_rule(&self, rule: &ProjectCodeRule) -> Result<()> {
+        let upsert_sql = r#"
+            INSERT INTO project_code_rules (kind, pattern, replacement, position)
+            VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT(kind, pattern) DO UPDATE SET
+                replacement = excluded.replacement,
+                position = excluded.position,
+                updated_at = CURRENT_TIMESTAMP
+        "#;
+
+        self.db.prepare(upsert_sql)
+            .bind(&[
+                rule.kind.as_str().into(),
+                rule.pattern.trim().into(),
+                rule.replacement.as_str().into(),
+                rule.position.into(),
+            ])?
+            .run().await
+            .map_err(|e| anyhow!("保存项目代号规则失败: {:?}", e))?;
+
+        Ok(())
+    }
+
+    /// 删除项目代号规则
+    pub async fn delete_project_code_rule(&self, id: i64) -> Result<()> {
+        self.db.prepare("DELETE FROM project_code_rules WHERE id = ?1")
+            .bind(&[(id as f64).into()])?
+            .run().await
+            .map_err(|e| anyhow!("删除项目代号规则失败: {:?}", e))?;
+
+        Ok(())
+    }
+
+    /// 导出所有可备份的表
+    pub async fn export_backup(&self) -> Result<Backup> {
+        let mut tables = BTreeMap::new();
+        for table in BACKUP_TABLES {
+            tables.insert(table.name.to_string(), self.export_table(table).await?);
+        }
+
+        Ok(Backup {
+            format_version: BACKUP_FORMAT_VERSION,
+            schema_version: backup::current_schema_version(),
+            exported_at: Some(Utc::now().to_rfc3339()),
+            tables,
+        })
+    }
+
+    /// 导出一张表的所有行
+    pub async fn export_table(&self, table: &BackupTable) -> Result<Vec<BackupRow>> {
+        let select_sql = format!("SELECT {} FROM {}", table.columns.join(", "), table.name);
+
+        let result = self.db.prepare(&select_sql)
+            .all().await
+            .map_err(|e| anyhow!("导出 {} 表失败: {:?}", table.name, e))?;
+
+        result.results::<BackupRow>()
+            .map_err(|e| anyhow!("解析 {} 表数据失败: {:?}", table.name, e))
+    }
+
+    /// 在一个 D1 batch 中导入备份，任何一行失败时整体回滚；调用前需先校验备份
+    /// 自增 id 不导入，由目标数据库重新生成；没有唯一约束的表按 natural_key 判断重复
+    pub async fn import_backup(&self, backup: &Backup, mode: ConflictMode) -> Result<ImportSummary> {
+        let mut statements = Vec::new();
+        let mut insert_indexes = Vec::new();
+        let mut conflict_checks = Vec::new();
+        let mut total = 0;
+        for table in BACKUP_TABLES {
+            let rows = match backup.tables.get(table.name) {
+                Some(rows) => rows,
+                None => continue,
+            };
+            for row in rows {
+                total += 1;
+
+                // 只写入行中出现的列，缺少的列使用表的默认值
+                let columns: Vec<&str> = table.columns.iter()
+                    .copied()
+                    .filter(|column| !table.generated.contains(column) && row.contains_key(*column))
+                    .collect();
+                let mut params: Vec<JsValue> = columns.iter()
+                    .map(|column| json_to_js(&row[*column]))
+                    .collect();
+                let placeholders = (1..=columns.len())
+                    .map(|i| format!("?{}", i))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                if table.natural_key.is_empty() {
+                    let insert_sql = format!(
+                        "{} INTO {} ({}) VALUES ({})",
+                        mode.insert_clause(), table.name, columns.join(", "), placeholders
+                    );
+                    insert_indexes.push(statements.len());
+                    statements.push(self.db.prepare(&insert_sql).bind(&params)?);
+                    continue;
+                }
+
+                // 按 natural_key 判断同一行是否已存在
+                let key_params: Vec<JsValue> = table.natural_key.iter()
+                    .map(|column| row.get(*column).map(json_to_js).unwrap_or(JsValue::NULL))
+                    .collect();
+                let key_condition = |offset: usize| table.natural_key.iter()
+                    .enumerate()
+                    .map(|(i, column)| format!("{} IS ?{}", column, offset + i + 1))
+                    .collect::<Vec<_>>()
+                    .join(" AND ");
+
+                match mode {
+                    ConflictMode::Skip => {}
+                    ConflictMode::Replace => {
+                        let delete_sql = format!("DELETE FROM {} WHERE {}", table.name, key_condition(0));
+                        statements.push(self.db.prepare(&delete_sql).bind(&key_params)?);
+                    }
+                    ConflictMode::Fail => {
+                        let check_sql = format!("SELECT COUNT(*) AS count FROM {} WHERE {}", table.name, key_condition(0));
+                        conflict_checks.push(self.db.prepare(&check_sql).bind(&key_params)?);
+                    }
+                }
+
+                let insert_sql = format!(
+                    "INSERT INTO {0} ({1}) SELECT {2} WHERE NOT EXISTS (SELECT 1 FROM {0} WHERE {3})",
+                    table.name, columns.join(", "), placeholders, key_condition(columns.len())
+                );
+                params.extend(key_params);
+                insert_indexes.push(statements.len());
+                statements.push(self.db.prepare(&insert_sql).bind(&params)?);
+            }
+        }
+
+        if statements.is_empty() {
+            return Ok(ImportSummary::default());
+        }
+
+        // fail 模式下先检查没有唯一约束的表中是否已有相同的行
+        if !conflict_checks.is_empty() {
+            let results = self.db.batch(conflict_checks).await
+                .map_err(|e| anyhow!("检查重复数据失败: {:?}", e))?;
+            let mut conflicts = 0;
+            for result in results {
+                let rows = result.results::<serde_json::Value>()
+                    .map_err(|e| anyhow!("检查重复数据失败: {:?}", e))?;
+                conflicts += rows.iter()
+                    .filter_map(|row| row.get("count").and_then(|count| count.as_u64()))
+                    .sum::<u64>();
+            }
+            if conflicts > 0 {
+                return Err(anyhow!("备份中有 {} 行与已有数据重复，已取消导入", conflicts));
+            }
+        }
+
+        let results = self.db.batch(statements).await
+            .map_err(|e| anyhow!("导入备份失败: {:?}", e))?;
+
+        // 冲突时被忽略的行不计入写入行数
+        let mut imported = 0;
+        for index in insert_indexes {
+            let meta = results.get(index)
+                .ok_or_else(|| anyhow!("导入备份失败: 未返回第 {} 条语句的结果", index + 1))?
+                .meta()
+                .map_err(|e| anyhow!("读取导入结果失败: {:?}", e))?;
+            imported += meta.and_then(|meta| meta.changes).unwrap_or(0);
+        }
+
+        Ok(ImportSummary {
+            imported,
+            skipped: total - imported,
+        })
+    }
+}
+
+/// 运行记录可以被重新抢占的条件，用于 ON CONFLICT DO UPDATE ... WHERE
+/// 失败或中断（运行中且超过 STALE_RUNNING_MINUTES 分钟未更新）的运行可以抢占；
+/// force 时除正在运行中的记录外都可以抢占，max_attempts 限制尝试次数
+fn claimable_condition(table: &str, force: bool, max_attempts: Option<i32>) -> String {
+    let running = RunStatus::Running.as_str();
+    let stale = format!("({table}.status = '{running}' AND {table}.updated_at < datetime('now', '-{STALE_RUNNING_MINUTES} minutes'))");
+    let mut condition = if force {
+        format!("({table}.status <> '{running}' OR {stale})")
+    } else {
+        format!("({table}.status = '{}' OR {stale})", RunStatus::Failed.as_str())
+    };
+    if let Some(max_attempts) = max_attempts {
+        condition.push_str(&format!(" AND {table}.attempts < {max_attempts}"));
+    }
+    condition
+}
+
+/// 写入语句实际修改的行数
+fn changed_rows(result: &D1Result) -> Result<usize> {
+    let meta = result.meta()
+        .map_err(|e| anyhow!("读取写入结果失败: {:?}", e))?;
+    Ok(meta.and_then(|meta| meta.changes).unwrap_or(0))
+}
+
+/// 备份中的 JSON 值转换为 D1 绑定参数
+fn json_to_js(value: &serde_json::Value) -> JsValue {
+    match value {
+        serde_json::Value::String(value) => value.as_str().into(),
+        serde_json::Value::Number(value) => value.as_f64().map(JsValue::from).unwrap_or(JsValue::NULL),
+        serde_json::Value::Bool(value) => (*value).into(),
+        _ => JsValue::NULL,
+    }
+}
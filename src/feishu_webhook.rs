@@ -2,6 +2,9 @@ use serde::{Deserialize, Serialize};
 use anyhow::{Result, anyhow};
 use worker::*;
 
+use crate::locale::Locale;
+use crate::team_digest::{DigestFormat, TeamDigest};
+
 #[derive(Serialize)]
 struct FeishuMessage {
     msg_type: String,
@@ -22,12 +25,13 @@ struct FeishuResponse {
 /// 飞书 Webhook 客户端
 pub struct FeishuWebhook {
     webhook_url: String,
+    locale: Locale,
 }
 
 impl FeishuWebhook {
     /// 创建新的飞书 Webhook 客户端
-    pub fn new(webhook_url: String) -> Self {
-        Self { webhook_url }
+    pub fn new(webhook_url: String, locale: Locale) -> Self {
+        Self { webhook_url, locale }
     }
 
     /// 发送文本消息到飞书
@@ -39,6 +43,11 @@ impl FeishuWebhook {
             },
         };
 
+        self.post(&message).await
+    }
+
+    /// 向 Webhook 发送消息体，并检查飞书 API 的返回码
+    async fn post<T: Serialize>(&self, message: &T) -> Result<()> {
         // 创建请求头
         let mut headers = worker::Headers::new();
         headers.set("Content-Type", "application/json")?;
@@ -46,7 +55,7 @@ impl FeishuWebhook {
         let mut request_init = RequestInit::new();
         request_init.method = Method::Post;
         request_init.headers = headers;
-        request_init.body = Some(serde_json::to_string(&message)?.into());
+        request_init.body = Some(serde_json::to_string(message)?.into());
 
         let request = Request::new_with_init(&self.webhook_url, &request_init)?;
 
@@ -75,11 +84,72 @@ impl FeishuWebhook {
         Ok(())
     }
 
-    /// 发送格式化的站会报告到飞书
-    pub async fn send_standup_report(&self, report: &str) -> Result<()> {
+    /// 发送飞书消息卡片
+    pub async fn send_card(&self, card: serde_json::Value) -> Result<()> {
+        let message = serde_json::json!({
+            "msg_type": "interactive",
+            "card": card
+        });
+        self.post(&message).await
+    }
+
+    /// 发送团队日报，卡片格式下每个成员一个分区
+    pub async fn send_team_digest(&self, digest: &TeamDigest, format: DigestFormat) -> Result<()> {
+        let texts = self.locale.texts();
+        match format {
+            DigestFormat::Text => self.send_titled_report(texts.digest_title, &digest.render_text(), None).await,
+            DigestFormat::Card => {
+                let mut elements = vec![serde_json::json!({
+                    "tag": "div",
+                    "text": { "tag": "lark_md", "content": digest.totals_line() }
+                })];
+                for (heading, body) in digest.sections() {
+                    elements.push(serde_json::json!({ "tag": "hr" }));
+                    elements.push(serde_json::json!({
+                        "tag": "div",
+                        "text": { "tag": "lark_md", "content": format!("**{}**\n{}", heading, body) }
+                    }));
+                }
+                elements.push(serde_json::json!({
+                    "tag": "note",
+                    "elements": [{
+                        "tag": "plain_text",
+                        "content": format!("{}{}", texts.generated_at, chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC"))
+                    }]
+                }));
+
+                self.send_card(serde_json::json!({
+                    "header": {
+                        "title": { "tag": "plain_text", "content": texts.digest_title },
+                        "template": "blue"
+                    },
+                    "elements": elements
+                })).await
+            }
+        }
+    }
+
+    /// 发送格式化的站会报告到飞书，member_name 不为空时在标题中标注成员名字
+    pub async fn send_standup_report(&self, report: &str, member_name: Option<&str>) -> Result<()> {
+        self.send_titled_report(self.locale.texts().feishu_title, report, member_name).await
+    }
+
+    /// 发送周报或月报到飞书
+    pub async fn send_summary_report(&self, title: &str, report: &str, member_name: Option<&str>) -> Result<()> {
+        self.send_titled_report(title, report, member_name).await
+    }
+
+    async fn send_titled_report(&self, title: &str, report: &str, member_name: Option<&str>) -> Result<()> {
+        let texts = self.locale.texts();
+        let title = match member_name {
+            Some(name) => format!("{} - {}", title, name),
+            None => title.to_string(),
+        };
         let formatted_message = format!(
-            "📋 每日站会报告\n{}\n\n⏰ 生成时间: {}",
+            "{}\n{}\n\n{}{}",
+            title,
             report,
+            texts.generated_at,
             chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC")
         );
 
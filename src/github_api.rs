@@ -1,9 +1,15 @@
 use serde::{Deserialize, Serialize};
-use chrono::{NaiveDate, Utc};
+use chrono::{NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::collections::HashMap;
 use anyhow::{Result, anyhow};
 use regex::Regex;
 use worker::*;
 
+use crate::locale::Locale;
+use crate::project_codes::ProjectCodes;
+use crate::taiga_projects::TaigaProjects;
+
 /// GitHub API 响应结构
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GitHubSearchResponse {
@@ -50,36 +56,188 @@ pub struct PullRequestInfo {
     pub merged_at: Option<String>,
 }
 
+/// 查询 PR 合并状态时只需要的字段
+#[derive(Debug, Deserialize)]
+struct PullRequestState {
+    merged_at: Option<String>,
+}
+
+/// PR 状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PrStatus {
+    Merged,
+    Closed,
+    Open,
+}
+
+impl PrStatus {
+    /// 当前语言下的状态文案
+    pub fn label(&self, locale: Locale) -> &'static str {
+        let texts = locale.texts();
+        match self {
+            PrStatus::Merged => texts.merged,
+            PrStatus::Closed => texts.closed,
+            PrStatus::Open => texts.open,
+        }
+    }
+}
+
+/// 单个 PR 整理后的站会数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StandupEntry {
+    pub title: String,
+    pub repo: String,
+    pub status: PrStatus,
+    pub taiga_id: Option<String>,
+    pub task_key: Option<String>,
+    pub days: Option<i32>,
+    /// 关联任务是否已完成（关联的 PR 全部合并）
+    #[serde(default)]
+    pub task_done: bool,
+    pub project_code: String,
+    pub work_summary: String,
+    pub html_url: String,
+}
+
+/// 站会报告数据：发送给 AI 的原始报告文本，以及用于校验的结构化数据
+#[derive(Debug, Clone)]
+pub struct StandupData {
+    pub report: String,
+    pub entries: Vec<StandupEntry>,
+    pub taiga_days_map: HashMap<String, i32>,
+}
+
+/// PR 引用的 Taiga 任务
+#[derive(Debug, Clone)]
+struct TaskRef {
+    taiga_id: String,
+    task_key: Option<String>,
+}
+
 /// GitHub API 客户端
 pub struct GitHubApiClient {
     token: String,
+    locale: Locale,
+    taiga_projects: TaigaProjects,
+    project_codes: ProjectCodes,
 }
 
 impl GitHubApiClient {
     /// 创建新的 GitHub API 客户端
-    pub fn new(token: String) -> Self {
-        Self { token }
+    pub fn new(token: String, locale: Locale) -> Self {
+        Self { token, locale, taiga_projects: TaigaProjects::default(), project_codes: ProjectCodes::default() }
     }
 
-    /// 获取当天创建的 PR
-    pub async fn get_today_prs(&self) -> Result<GitHubSearchResponse> {
-        // 获取当前日期（UTC时间）
-        let today = Utc::now().format("%Y-%m-%d").to_string();
-        self.get_prs_by_date(&today).await
+    /// 设置仓库到项目代号的规则，未设置时使用仓库名
+    pub fn with_project_codes(mut self, project_codes: ProjectCodes) -> Self {
+        self.project_codes = project_codes;
+        self
     }
 
-    /// 获取指定日期创建的 PR
-    pub async fn get_prs_by_date(&self, date: &str) -> Result<GitHubSearchResponse> {
-        // 验证日期格式
-        if NaiveDate::parse_from_str(date, "%Y-%m-%d").is_err() {
-            return Err(anyhow!("日期格式无效，请使用 YYYY-MM-DD 格式"));
-        }
+    /// 设置仓库到 Taiga 项目的映射，用于判断 PR 中的 #N 是否为 Taiga 任务编号
+    pub fn with_taiga_projects(mut self, taiga_projects: TaigaProjects) -> Self {
+        self.taiga_projects = taiga_projects;
+        self
+    }
+
+    /// 获取指定用户在其所在时区某一天创建的 PR，author 为 "@me" 时表示 Token 对应的用户
+    pub async fn get_prs_by_date(&self, date: NaiveDate, tz: Tz, author: &str) -> Result<GitHubSearchResponse> {
+        // 将本地日期换算为 UTC 时间范围
+        let start = local_day_start(date, tz)?;
+        let next_day = date.succ_opt().ok_or_else(|| anyhow!("日期超出范围: {}", date))?;
+        let end = local_day_start(next_day, tz)? - chrono::Duration::seconds(1);
 
         let url = format!(
-            "https://api.github.com/search/issues?q=is:pr+author:@me+created:{}",
-            date
+            "https://api.github.com/search/issues?q=is:pr+author:{}+created:{}..{}",
+            author,
+            start.format("%Y-%m-%dT%H:%M:%SZ"),
+            end.format("%Y-%m-%dT%H:%M:%SZ")
         );
 
+        self.get_json(&url).await
+    }
+
+    /// 获取 Token 对应的 GitHub 用户
+    pub async fn get_authenticated_user(&self) -> Result<User> {
+        self.get_json("https://api.github.com/user").await
+    }
+
+    /// 仓库中是否存在编号为 number 的 issue 或 PR
+    pub async fn issue_exists(&self, repo: &str, number: u32) -> Result<bool> {
+        let url = format!("https://api.github.com/repos/{}/issues/{}", repo, number);
+        let mut response = self.get(&url).await?;
+
+        match response.status_code() {
+            200..=299 => Ok(true),
+            // 404 表示不存在，410 表示 issue 已被删除
+            404 | 410 => Ok(false),
+            status => {
+                let error_text = response.text().await.unwrap_or_default();
+                Err(anyhow!("GitHub API 请求失败: {} - {}", status, error_text))
+            }
+        }
+    }
+
+    /// PR 的合并时间，未合并时返回 None
+    pub async fn pr_merged_at(&self, repo: &str, number: u32) -> Result<Option<String>> {
+        let url = format!("https://api.github.com/repos/{}/pulls/{}", repo, number);
+        let pr: PullRequestState = self.get_json(&url).await?;
+        Ok(pr.merged_at)
+    }
+
+    /// 引用任务的全部 PR 都已合并的任务
+    /// 除今天的 PR 外，还检查之前记录的关联：记录为未合并的 PR 向 GitHub 查询最新状态并写回数据库，
+    /// 查询失败时按未合并处理
+    async fn completed_task_keys(&self, db_client: &crate::database::DatabaseClient<'_>, task_keys: &[String], today_links: &[crate::database::PrTaskLink]) -> Result<Vec<String>> {
+        let links = db_client.list_pr_task_links(task_keys).await?;
+
+        let mut merged_prs: HashMap<(String, u32), bool> = today_links.iter()
+            .map(|link| ((link.repo.clone(), link.pr_number), link.merged_at.is_some()))
+            .collect();
+        let mut task_merged: HashMap<String, bool> = HashMap::new();
+        for link in links.iter().chain(today_links) {
+            let pr = (link.repo.clone(), link.pr_number);
+            let merged = match merged_prs.get(&pr) {
+                Some(merged) => *merged,
+                None if link.merged_at.is_some() => true,
+                None => match self.pr_merged_at(&link.repo, link.pr_number).await {
+                    Ok(Some(merged_at)) => {
+                        db_client.set_pr_merged_at(&link.repo, link.pr_number, &merged_at).await?;
+                        true
+                    }
+                    Ok(None) => false,
+                    Err(e) => {
+                        console_log!("⚠️ 查询 {}#{} 的合并状态失败: {}", link.repo, link.pr_number, e);
+                        false
+                    }
+                },
+            };
+            merged_prs.insert(pr, merged);
+            *task_merged.entry(link.task_key.clone()).or_insert(true) &= merged;
+        }
+
+        Ok(task_merged.into_iter()
+            .filter(|(_, merged)| *merged)
+            .map(|(key, _)| key)
+            .collect())
+    }
+
+    /// 发起 GitHub API GET 请求并解析 JSON 响应
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T> {
+        let mut response = self.get(url).await?;
+
+        if !(200..300).contains(&response.status_code()) {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow!("GitHub API 请求失败: {} - {}", response.status_code(), error_text));
+        }
+
+        let body: T = response.json().await?;
+        Ok(body)
+    }
+
+    /// 发起 GitHub API GET 请求
+    async fn get(&self, url: &str) -> Result<Response> {
         // 创建请求头
         let mut headers = worker::Headers::new();
         headers.set("User-Agent", "auto-daily-standup-worker")?;
@@ -90,154 +248,214 @@ impl GitHubApiClient {
         request_init.method = Method::Get;
         request_init.headers = headers;
 
-        let request = Request::new_with_init(&url, &request_init)?;
-
-        let mut response = Fetch::Request(request).send().await?;
+        let request = Request::new_with_init(url, &request_init)?;
 
-        if !(200..300).contains(&response.status_code()) {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(anyhow!("GitHub API 请求失败: {} - {}", response.status_code(), error_text));
-        }
-
-        let search_response: GitHubSearchResponse = response.json().await?;
-        Ok(search_response)
+        Ok(Fetch::Request(request).send().await?)
     }
 
-    /// 生成每日站会报告格式
-    pub async fn generate_standup_report(&self, response: &GitHubSearchResponse, db_client: Option<&crate::database::DatabaseClient<'_>>) -> String {
+    /// 生成每日站会报告格式，day_context 为报告所属成员的工作日日历和请假信息，用于累积任务天数
+    /// 任务天数更新失败时返回错误，不使用不准确的天数生成报告
+    pub async fn generate_standup_report(&self, response: &GitHubSearchResponse, day_context: &crate::database::TaskDayContext<'_>, db_client: Option<&crate::database::DatabaseClient<'_>>) -> Result<StandupData> {
         let mut report = String::new();
+        let mut entries = Vec::new();
+        let mut taiga_days_map = HashMap::new();
+        let texts = self.locale.texts();
         
-        report.push_str("=== 每日站会报告数据 ===\n\n");
-        report.push_str("## 原始 GitHub PR 数据摘要\n");
-        report.push_str(&format!("- 今日创建的 PR 总数：{}\n", response.total_count));
-        report.push_str(&format!("- 数据完整性：{}\n\n", if response.incomplete_results { "部分数据" } else { "完整数据" }));
+        report.push_str(&format!("{}\n\n", texts.data_header));
+        report.push_str(&format!("{}\n", texts.summary_header));
+        report.push_str(&format!("{}{}\n", texts.total_prs, response.total_count));
+        report.push_str(&format!("{}{}\n\n", texts.completeness, if response.incomplete_results { texts.partial_data } else { texts.complete_data }));
 
         if response.items.is_empty() {
-            report.push_str("今天没有创建任何 PR，可能没有代码提交工作完成。\n\n");
+            report.push_str(&format!("{}\n\n", texts.no_prs));
         } else {
-            report.push_str("## PR 详细信息\n\n");
+            report.push_str(&format!("{}\n\n", texts.details_header));
             
-            // 收集所有的 Taiga URLs 用于批量处理
-            let mut taiga_urls = Vec::new();
+            // 解析每个 PR 引用的 Taiga 任务，同时记录每个任务关联的 PR 是否全部已合并
+            let mut task_refs = Vec::new();
+            let mut task_keys: Vec<String> = Vec::new();
+            let mut pr_links = Vec::new();
+            let mut issue_cache = HashMap::new();
+            let today = day_context.today.format("%Y-%m-%d").to_string();
             for pr in &response.items {
-                let body_content = pr.body.as_deref().unwrap_or("");
-                let combined_text = format!("{} {}", pr.title, body_content);
-                
-                // 查找 Taiga URLs
-                let taiga_url_regex = regex::Regex::new(r"https://[^\s]+\.taiga\.io/project/[^/]+/task/\d+").unwrap();
-                for mat in taiga_url_regex.find_iter(&combined_text) {
-                    taiga_urls.push(mat.as_str().to_string()); // 转换为拥有所有权的字符串
+                let repo = pr.repository_url.replace("https://api.github.com/repos/", "");
+                let refs = self.resolve_task_refs(pr, &repo, &mut issue_cache).await;
+
+                for key in refs.iter().filter_map(|task_ref| task_ref.task_key.as_ref()) {
+                    if !task_keys.contains(key) {
+                        task_keys.push(key.clone());
+                    }
+                    pr_links.push(crate::database::PrTaskLink {
+                        repo: repo.clone(),
+                        pr_number: pr.number,
+                        task_key: key.clone(),
+                        first_linked_date: today.clone(),
+                        merged_at: pr.pull_request.merged_at.clone(),
+                    });
                 }
+                task_refs.push(refs);
             }
+            let mut done_keys = std::collections::HashSet::new();
             
-            // 处理 Taiga URLs 并获取天数信息
-            let mut taiga_days_map = std::collections::HashMap::new();
+            // 处理 Taiga 任务并获取天数信息
             if let Some(db_client) = db_client {
-                // 去重URLs
-                let unique_urls: Vec<&str> = taiga_urls.iter().map(|s| s.as_str()).collect::<std::collections::HashSet<_>>().into_iter().collect();
-                
-                match db_client.process_taiga_urls(unique_urls).await {
-                    Ok(results) => {
-                        for (task_key, days) in results {
-                            taiga_days_map.insert(task_key, days);
-                        }
-                        console_log!("✓ 成功处理 {} 个 Taiga 任务", taiga_days_map.len());
-                    }
-                    Err(e) => {
-                        console_log!("⚠️ 处理 Taiga 任务失败: {}", e);
+                // 先记录 PR 与任务的关联，失败不影响报告生成
+                if let Err(e) = db_client.record_pr_task_links(&pr_links).await {
+                    console_log!("⚠️ 记录 PR 与任务的关联失败: {}", e);
+                }
+
+                // 引用任务的全部 PR（包括之前记录的关联）都已合并时，任务标记为完成
+                let completed_keys = self.completed_task_keys(db_client, &task_keys, &pr_links).await
+                    .map_err(|e| anyhow!("检查任务关联的 PR 是否全部合并失败: {}", e))?;
+                let records = db_client.process_task_keys(&task_keys, &completed_keys, day_context).await
+                    .map_err(|e| anyhow!("处理 Taiga 任务失败: {}", e))?;
+                for record in records {
+                    if record.status == crate::database::TaskStatus::Done {
+                        done_keys.insert(record.task_key.clone());
                     }
+                    taiga_days_map.insert(record.task_key, record.total_days);
                 }
+                console_log!("✓ 成功处理 {} 个 Taiga 任务", taiga_days_map.len());
             }
             
-            for (index, pr) in response.items.iter().enumerate() {
+            for (index, (pr, refs)) in response.items.iter().zip(task_refs).enumerate() {
                 report.push_str(&format!("### PR #{}\n", index + 1));
-                report.push_str(&format!("- 标题：{}\n", pr.title));
-                report.push_str(&format!("- 仓库：{}\n", 
-                    pr.repository_url.replace("https://api.github.com/repos/", "")));
-                report.push_str(&format!("- 状态：{}\n", 
-                    if let Some(_) = &pr.pull_request.merged_at {
-                        "已合并"
-                    } else if pr.state == "closed" {
-                        "已关闭"
-                    } else {
-                        "进行中"
-                    }
-                ));
+                let repo = pr.repository_url.replace("https://api.github.com/repos/", "");
+                let status = if pr.pull_request.merged_at.is_some() {
+                    PrStatus::Merged
+                } else if pr.state == "closed" {
+                    PrStatus::Closed
+                } else {
+                    PrStatus::Open
+                };
+                report.push_str(&format!("{}{}\n", texts.title, pr.title));
+                report.push_str(&format!("{}{}\n", texts.repo, repo));
+                report.push_str(&format!("{}{}\n", texts.status, status.label(self.locale)));
                 
-                // 尝试从标题和描述中提取 Taiga issue 信息
+                // 报告中展示第一个引用的 Taiga 任务
                 let body_content = pr.body.as_deref().unwrap_or("");
-                let taiga_info = self.extract_taiga_info(&pr.title, body_content);
-                if !taiga_info.is_empty() {
-                    // 查找对应的 Taiga URL 并获取任务键
-                    let combined_text = format!("{} {}", pr.title, body_content);
-                    let taiga_url_regex = regex::Regex::new(r"https://[^\s]+\.taiga\.io/project/[^/]+/task/\d+").unwrap();
-                    
+                let first_ref = refs.into_iter().next();
+                let taiga_id = first_ref.as_ref().map(|task_ref| task_ref.taiga_id.clone());
+                let task_key = first_ref.and_then(|task_ref| task_ref.task_key);
+                let mut days = None;
+                let mut task_done = false;
+                if let Some(taiga_id) = &taiga_id {
                     let mut days_info = String::new();
-                    if let Some(mat) = taiga_url_regex.find(&combined_text) {
-                        if let Some(task_key) = crate::database::DatabaseClient::extract_task_key_from_url(mat.as_str()) {
-                            if let Some(days) = taiga_days_map.get(&task_key) {
-                                days_info = format!(" (累积{}天)", days);
-                            }
+                    if let Some(key) = &task_key {
+                        if let Some(task_days) = taiga_days_map.get(key) {
+                            days_info = format!("{}{}{}", texts.days_prefix, task_days, texts.days_suffix);
+                            days = Some(*task_days);
+                        }
+                        if done_keys.contains(key) {
+                            days_info.push_str(texts.task_done);
+                            task_done = true;
                         }
                     }
                     
-                    report.push_str(&format!("- 关联 Taiga：{}{}\n", taiga_info, days_info));
+                    report.push_str(&format!("{}Task #{}{}\n", texts.taiga, taiga_id, days_info));
                 }
                 
-                // 提取项目代号
-                let project_code = self.extract_project_code(&pr.repository_url);
+                // 按规则计算项目代号
+                let project_code = self.project_codes.code_for(&repo);
                 if !project_code.is_empty() {
-                    report.push_str(&format!("- 项目代号：{}\n", project_code));
+                    report.push_str(&format!("{}{}\n", texts.project_code, project_code));
                 }
                 
                 // 提取工作总结
                 let work_summary = self.extract_work_summary(body_content);
                 if !work_summary.is_empty() {
-                    report.push_str(&format!("- 工作内容：{}\n", work_summary));
+                    report.push_str(&format!("{}{}\n", texts.work_summary, work_summary));
                 }
                 
-                report.push_str(&format!("- 链接：{}\n", pr.html_url));
+                report.push_str(&format!("{}{}\n", texts.link, pr.html_url));
                 report.push('\n');
+
+                entries.push(StandupEntry {
+                    title: pr.title.clone(),
+                    repo,
+                    status,
+                    taiga_id,
+                    task_key,
+                    days,
+                    task_done,
+                    project_code,
+                    work_summary,
+                    html_url: pr.html_url.clone(),
+                });
             }
         }
 
         // 添加 AI 处理提示
-        report.push_str("## AI 处理指引\n");
+        report.push_str(&format!("{}\n", texts.ai_guide_header));
         report.push_str(&self.generate_ai_prompt());
         
-        report
+        Ok(StandupData {
+            report,
+            entries,
+            taiga_days_map,
+        })
     }
 
-    /// 从标题和 PR 描述中提取 Taiga issue 信息
-    fn extract_taiga_info(&self, title: &str, body: &str) -> String {
-        let combined_text = format!("{} {}", title, body);
-        
+    /// 从标题和 PR 描述中提取引用的 Taiga 任务
+    /// 优先使用 Taiga URL；没有 URL 时，只有仓库配置了 Taiga 项目映射，
+    /// 且 #N 在仓库中不是 GitHub issue 或 PR 时，才把 #N 当作 Taiga 任务编号
+    async fn resolve_task_refs(&self, pr: &PullRequestItem, repo: &str, issue_cache: &mut HashMap<(String, u32), bool>) -> Vec<TaskRef> {
+        let body_content = pr.body.as_deref().unwrap_or("");
+        let combined_text = format!("{} {}", pr.title, body_content);
+
         // 匹配 Taiga URL 模式
         let taiga_url_regex = Regex::new(r"https://[^\s/]+\.taiga\.io/project/[^/]+/task/(\d+)").unwrap();
-        if let Some(captures) = taiga_url_regex.captures(&combined_text) {
-            if let Some(task_id) = captures.get(1) {
-                return format!("Task #{}", task_id.as_str());
-            }
+        let url_refs: Vec<TaskRef> = taiga_url_regex.captures_iter(&combined_text)
+            .map(|captures| TaskRef {
+                taiga_id: captures[1].to_string(),
+                task_key: crate::database::DatabaseClient::extract_task_key_from_url(&captures[0]),
+            })
+            .collect();
+        if !url_refs.is_empty() {
+            return url_refs;
         }
-        
-        // 匹配 #数字 模式
+
+        let project = match self.taiga_projects.project_for(repo) {
+            Some(project) => project,
+            None => return Vec::new(),
+        };
+
+        // 匹配 #数字 模式，逐个向 GitHub 确认是否为仓库中的 issue 或 PR
         let hash_number_regex = Regex::new(r"#(\d+)").unwrap();
-        if let Some(captures) = hash_number_regex.captures(&combined_text) {
-            if let Some(task_id) = captures.get(1) {
-                return format!("Task #{}", task_id.as_str());
+        let mut refs: Vec<TaskRef> = Vec::new();
+        for captures in hash_number_regex.captures_iter(&combined_text) {
+            let number = match captures[1].parse::<u32>() {
+                Ok(number) => number,
+                Err(_) => continue,
+            };
+            if refs.iter().any(|task_ref| task_ref.taiga_id == captures[1]) {
+                continue;
             }
-        }
-        
-        String::new()
-    }
 
-    /// 从仓库 URL 中提取项目代号
-    fn extract_project_code(&self, repo_url: &str) -> String {
-        // 从 GitHub 仓库 URL 中取项目名称作为代号
-        if let Some(repo_name) = repo_url.split('/').last() {
-            return repo_name.to_uppercase();
+            let cache_key = (repo.to_string(), number);
+            let is_github_ref = match issue_cache.get(&cache_key) {
+                Some(exists) => *exists,
+                None => match self.issue_exists(repo, number).await {
+                    Ok(exists) => {
+                        issue_cache.insert(cache_key, exists);
+                        exists
+                    }
+                    Err(e) => {
+                        console_log!("⚠️ 查询 {}#{} 失败，不作为 Taiga 任务: {}", repo, number, e);
+                        continue;
+                    }
+                },
+            };
+
+            if !is_github_ref {
+                refs.push(TaskRef {
+                    taiga_id: number.to_string(),
+                    task_key: Some(format!("{}#{}", project, number)),
+                });
+            }
         }
-        String::new()
+
+        refs
     }
 
     /// 从 PR 描述中提取工作总结
@@ -264,34 +482,15 @@ impl GitHubApiClient {
 
     /// 生成 AI 处理提示
     fn generate_ai_prompt(&self) -> String {
-        r#"请基于上述 GitHub PR 数据，生成符合以下格式的每日站会报告：
-
-格式要求：
-- 有对应 Taiga issue 的：[天数]项目代号#Taiga编号-工作内容
-- 无对应 Taiga issue 的：[天数]工作内容
-- [天数] 是指这项工作到目前为止累积的天数（在原始数据中已经标注了累积天数）
-
-示例：
-[2]xxxAsk#3-重构登录逻辑
-[1]ktv#15-完成功能开发，准备测试
-[3]学习Flutter和Dart
-
-生成要求：
-
-1. 内容应简洁明了，避免冗长描述
-2. 机密项目应避免透露敏感信息
-3. 耗时一小时以下的工作无需汇报
-4. 根据 PR 状态推断工作进度：
-   - 已合并的 PR = 工作已完成
-   - 进行中的 PR = 工作正在进行
-   - 已关闭未合并的 PR = 工作可能取消或需要重新开始
-5. 请自动提取项目代号，去除项目代号中无关部分，例如组织名、子模块名等等，例如 XXX-International-Corp/XXXX_flutter 提取为 XXXX，去除了XXX-International-Corp/和_flutter
-6. 项目代号不要全部大写！
-7. 优先使用原始数据中的累积天数信息，如果原始数据中标注了"(累积X天)"，则使用该天数
-8. 如果没有累积天数信息，根据 PR 状态推断是新工作还是持续工作
-
-请基于上述数据生成今日的站会报告内容。
-请输出纯文本，不要使用 markdown，不要包含任何 markdown 语法。
-为了避免涉密，请不要输出任何与项目相关的信息，尽量简要描述今天的工作内容就够了。"#.to_string()
+        self.locale.texts().ai_prompt.to_string()
     }
-} 
\ No newline at end of file
+}
+
+/// 本地日期零点对应的 UTC 时间，零点因夏令时不存在时取当天最早的有效时间
+fn local_day_start(date: NaiveDate, tz: Tz) -> Result<chrono::DateTime<Utc>> {
+    (0..24)
+        .filter_map(|hour| date.and_hms_opt(hour, 0, 0))
+        .find_map(|time| tz.from_local_datetime(&time).earliest())
+        .map(|start| start.with_timezone(&Utc))
+        .ok_or_else(|| anyhow!("无法计算 {} 在时区 {} 的起始时间", date, tz))
+}
@@ -0,0 +1,241 @@
+use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use chrono::{Datelike, NaiveDate};
+use anyhow::{Result, anyhow};
+use worker::*;
+
+use crate::database::DatabaseClient;
+
+/// 默认的节假日 API 地址，仅用于补全本地缺失的年份
+const DEFAULT_HOLIDAY_API_URL: &str = "https://api.haoshenqi.top/holiday";
+
+/// 日期状态，与节假日 API 的 status 取值一致
+/// 0 普通工作日, 1 周末双休日, 2 需要补班的工作日, 3 法定节假日
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DayStatus {
+    Workday,
+    Weekend,
+    MakeupWorkday,
+    Holiday,
+}
+
+impl DayStatus {
+    pub fn from_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(DayStatus::Workday),
+            1 => Some(DayStatus::Weekend),
+            2 => Some(DayStatus::MakeupWorkday),
+            3 => Some(DayStatus::Holiday),
+            _ => None,
+        }
+    }
+
+    pub fn code(&self) -> i32 {
+        match self {
+            DayStatus::Workday => 0,
+            DayStatus::Weekend => 1,
+            DayStatus::MakeupWorkday => 2,
+            DayStatus::Holiday => 3,
+        }
+    }
+
+    /// 只有普通工作日和补班日需要上班
+    pub fn is_working_day(&self) -> bool {
+        matches!(self, DayStatus::Workday | DayStatus::MakeupWorkday)
+    }
+
+    /// 不在日历中的日期，按周一到周五上班处理
+    pub fn default_for(date: NaiveDate) -> Self {
+        if date.weekday().number_from_monday() <= 5 {
+            DayStatus::Workday
+        } else {
+            DayStatus::Weekend
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            DayStatus::Workday => "普通工作日",
+            DayStatus::Weekend => "周末双休日",
+            DayStatus::MakeupWorkday => "需要补班的工作日",
+            DayStatus::Holiday => "法定节假日",
+        }
+    }
+}
+
+/// 本地日历中的一条记录，只保存与默认规则（周一到周五上班）不同的日期
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HolidayCalendarEntry {
+    pub date: String,       // YYYY-MM-DD
+    pub status: i32,        // 2 补班日 或 3 法定节假日
+    pub name: Option<String>,
+}
+
+/// 导入的日历日期
+#[derive(Debug, Clone, Deserialize)]
+pub struct CalendarDay {
+    pub date: String,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// 一年的日历导入数据
+/// holidays 为法定节假日（含调休放假的工作日），workdays 为调休补班的周末
+#[derive(Debug, Clone, Deserialize)]
+pub struct CalendarImport {
+    pub year: i32,
+    #[serde(default)]
+    pub holidays: Vec<CalendarDay>,
+    #[serde(default)]
+    pub workdays: Vec<CalendarDay>,
+}
+
+impl CalendarImport {
+    /// 校验导入数据并转换为日历记录
+    pub fn into_entries(self) -> Result<Vec<HolidayCalendarEntry>> {
+        let mut entries = Vec::new();
+        let days = self.holidays.into_iter().map(|d| (d, DayStatus::Holiday))
+            .chain(self.workdays.into_iter().map(|d| (d, DayStatus::MakeupWorkday)));
+
+        for (day, status) in days {
+            let date = NaiveDate::parse_from_str(&day.date, "%Y-%m-%d")
+                .map_err(|_| anyhow!("日期格式无效: {}，请使用 YYYY-MM-DD 格式", day.date))?;
+            if date.year() != self.year {
+                return Err(anyhow!("日期 {} 不属于 {} 年", day.date, self.year));
+            }
+            if entries.iter().any(|e: &HolidayCalendarEntry| e.date == day.date) {
+                return Err(anyhow!("日期 {} 重复出现", day.date));
+            }
+            entries.push(HolidayCalendarEntry {
+                date: day.date,
+                status: status.code(),
+                name: day.name,
+            });
+        }
+
+        Ok(entries)
+    }
+}
+
+/// 节假日 API 的响应
+#[derive(Serialize, Deserialize)]
+struct HolidayResponse {
+    date: String,
+    year: i32,
+    month: i32,
+    day: i32,
+    status: i32,
+}
+
+/// 工作日日历：优先查询 D1 中的本地日历，缺失的年份通过节假日 API 补全
+pub struct HolidayCalendar<'a> {
+    db_client: &'a DatabaseClient<'a>,
+    api_url: String,
+}
+
+impl<'a> HolidayCalendar<'a> {
+    pub fn new(db_client: &'a DatabaseClient<'a>, api_url: Option<String>) -> Self {
+        Self {
+            db_client,
+            api_url: api_url.unwrap_or_else(|| DEFAULT_HOLIDAY_API_URL.to_string()),
+        }
+    }
+
+    /// 查询指定日期的状态
+    pub async fn day_status(&self, date: NaiveDate) -> Result<DayStatus> {
+        self.ensure_year(date.year()).await?;
+
+        let date_str = date.format("%Y-%m-%d").to_string();
+        match self.db_client.get_holiday_entry(&date_str).await? {
+            Some(entry) => DayStatus::from_code(entry.status)
+                .ok_or_else(|| anyhow!("未知的工作日状态: {}", entry.status)),
+            None => Ok(DayStatus::default_for(date)),
+        }
+    }
+
+    /// 查询日期范围内（包含首尾两天）需要上班的日期，按年批量读取本地日历
+    pub async fn working_days(&self, start: NaiveDate, end: NaiveDate) -> Result<HashSet<NaiveDate>> {
+        let mut statuses = HashMap::new();
+        for year in start.year()..=end.year() {
+            self.ensure_year(year).await?;
+            for entry in self.db_client.list_holiday_year(year).await? {
+                let date = NaiveDate::parse_from_str(&entry.date, "%Y-%m-%d")
+                    .map_err(|_| anyhow!("日历日期格式无效: {}", entry.date))?;
+                let status = DayStatus::from_code(entry.status)
+                    .ok_or_else(|| anyhow!("未知的工作日状态: {}", entry.status))?;
+                statuses.insert(date, status);
+            }
+        }
+
+        let mut days = HashSet::new();
+        let mut current = start;
+        while current <= end {
+            let status = statuses.get(&current).copied().unwrap_or_else(|| DayStatus::default_for(current));
+            if status.is_working_day() {
+                days.insert(current);
+            }
+            current = match current.succ_opt() {
+                Some(next) => next,
+                None => break,
+            };
+        }
+
+        Ok(days)
+    }
+
+    /// 本地日历缺少某一年时，通过节假日 API 补全
+    async fn ensure_year(&self, year: i32) -> Result<()> {
+        if !self.db_client.has_holiday_year(year).await? {
+            console_log!("本地日历缺少 {} 年数据，正在从节假日 API 补全...", year);
+            let entries = self.fetch_remote_year(year).await?;
+            let count = self.db_client.import_holiday_year(year, &entries, "api").await?;
+            console_log!("✓ 已补全 {} 年日历，共 {} 个节假日/补班日", year, count);
+        }
+        Ok(())
+    }
+
+    /// 从节假日 API 获取一整年的数据，只保留与默认规则不同的日期
+    async fn fetch_remote_year(&self, year: i32) -> Result<Vec<HolidayCalendarEntry>> {
+        let api_url = format!("{}?date={}", self.api_url, year);
+
+        let mut init = RequestInit::new();
+        init.with_method(Method::Get);
+
+        let request = Request::new_with_init(&api_url, &init)?;
+        let mut response = Fetch::Request(request).send().await?;
+
+        if response.status_code() != 200 {
+            return Err(anyhow!("节假日API请求失败，状态码: {}", response.status_code()));
+        }
+
+        let response_text = response.text().await?;
+        let holiday_responses: Vec<HolidayResponse> = serde_json::from_str(&response_text)
+            .map_err(|e| anyhow!("解析节假日API响应失败: {}", e))?;
+
+        if holiday_responses.is_empty() {
+            return Err(anyhow!("节假日API未返回 {} 年的数据", year));
+        }
+
+        let mut entries = Vec::new();
+        for day in holiday_responses {
+            let date = match NaiveDate::from_ymd_opt(day.year, day.month as u32, day.day as u32) {
+                Some(date) if date.year() == year => date,
+                _ => continue,
+            };
+            let status = match DayStatus::from_code(day.status) {
+                Some(status) => status,
+                None => continue,
+            };
+            let default_status = DayStatus::default_for(date);
+            if status.is_working_day() != default_status.is_working_day() || status == DayStatus::Holiday {
+                entries.push(HolidayCalendarEntry {
+                    date: date.format("%Y-%m-%d").to_string(),
+                    status: status.code(),
+                    name: None,
+                });
+            }
+        }
+
+        Ok(entries)
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDate;
+use anyhow::{Result, anyhow};
+
+use crate::calendar::parse_ics_events;
+use crate::team::TOKEN_OWNER;
+
+/// ICS 导入时识别为请假的关键词，飞书日历导出的请假日程标题通常包含这些字样
+const LEAVE_KEYWORDS: &[&str] = &[
+    "请假", "休假", "年假", "病假", "事假", "调休", "婚假", "产假", "陪产假",
+    "OOO", "Out of Office", "Leave", "Vacation", "PTO",
+];
+
+fn default_source() -> String {
+    "manual".to_string()
+}
+
+/// 个人请假记录，日期范围包含首尾两天
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeaveRecord {
+    #[serde(default)]
+    pub id: Option<i64>,
+    pub login: String,               // GitHub 用户名
+    pub start_date: String,          // 开始日期 YYYY-MM-DD
+    pub end_date: String,            // 结束日期 YYYY-MM-DD
+    #[serde(default)]
+    pub reason: Option<String>,
+    #[serde(default = "default_source")]
+    pub source: String,              // manual 或 ics
+}
+
+impl LeaveRecord {
+    /// 校验并解析请假日期范围
+    pub fn date_range(&self) -> Result<(NaiveDate, NaiveDate)> {
+        if self.login.trim().is_empty() || self.login == TOKEN_OWNER {
+            return Err(anyhow!("login 不能为空，请填写 GitHub 用户名"));
+        }
+        let start = NaiveDate::parse_from_str(&self.start_date, "%Y-%m-%d")
+            .map_err(|_| anyhow!("开始日期格式无效: {}，请使用 YYYY-MM-DD 格式", self.start_date))?;
+        let end = NaiveDate::parse_from_str(&self.end_date, "%Y-%m-%d")
+            .map_err(|_| anyhow!("结束日期格式无效: {}，请使用 YYYY-MM-DD 格式", self.end_date))?;
+        if end < start {
+            return Err(anyhow!("结束日期 {} 早于开始日期 {}", self.end_date, self.start_date));
+        }
+        Ok((start, end))
+    }
+}
+
+/// 展开请假记录中的全部日期，日期无效的记录忽略
+pub fn leave_days(leaves: &[LeaveRecord]) -> HashSet<NaiveDate> {
+    let mut days = HashSet::new();
+    for leave in leaves {
+        let (start, end) = match leave.date_range() {
+            Ok(range) => range,
+            Err(_) => continue,
+        };
+        let mut current = start;
+        while current <= end {
+            days.insert(current);
+            current = match current.succ_opt() {
+                Some(next) => next,
+                None => break,
+            };
+        }
+    }
+    days
+}
+
+/// 从 ICS 文件（例如飞书日历导出）中提取请假记录
+/// 默认只导入标题或分类包含请假关键词的日程，all_events 为 true 时导入全部日程
+pub fn leave_from_ics(login: &str, content: &str, all_events: bool) -> Result<Vec<LeaveRecord>> {
+    let events = parse_ics_events(content)?;
+
+    Ok(events.into_iter()
+        .filter(|event| {
+            if all_events {
+                return true;
+            }
+            let label = format!("{} {}", event.summary, event.categories).to_lowercase();
+            LEAVE_KEYWORDS.iter().any(|keyword| label.contains(&keyword.to_lowercase()))
+        })
+        .map(|event| LeaveRecord {
+            id: None,
+            login: login.to_string(),
+            start_date: event.start.format("%Y-%m-%d").to_string(),
+            end_date: event.end.format("%Y-%m-%d").to_string(),
+            reason: Some(event.summary).filter(|summary| !summary.is_empty()),
+            source: "ics".to_string(),
+        })
+        .collect())
+}
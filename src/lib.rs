@@ -1,26 +1,57 @@
 use worker::*;
-use serde_json;
-use serde::{Deserialize, Serialize};
-use wasm_bindgen::JsValue;
 
 mod github_api;
 mod ai_client;
 mod feishu_webhook;
 mod database;
+mod ai_usage;
+mod redaction;
+mod report_validator;
+mod locale;
+mod holiday_calendar;
+mod calendar;
+mod team;
+mod admin_api;
+mod leave;
+mod migrations;
+mod standup_run;
+mod report_history;
+mod summary_report;
+mod team_digest;
+mod task_store;
+mod backup;
+mod taiga_projects;
+mod project_codes;
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg_attr(not(test), allow(dead_code))]
+mod memory_task_store;
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg_attr(not(test), allow(dead_code))]
+mod sqlite_task_store;
 
 use github_api::GitHubApiClient;
-use ai_client::AIClient;
+use ai_client::{AIClient, StreamChunk};
 use feishu_webhook::FeishuWebhook;
-use database::DatabaseClient;
+use database::{DatabaseClient, TaskDayContext, TaskDaysMode};
+use ai_usage::{AIUsageRecord, PriceTable};
+use redaction::{RedactionRules, Redactor, StreamingRestorer};
+use futures::StreamExt;
+use report_validator::ReportValidator;
+use locale::Locale;
+use holiday_calendar::DayStatus;
+use calendar::{create_calendar, WeekendCalendar, WorkingCalendar};
+use team::TeamMember;
+use admin_api::query_param;
+use standup_run::RunStatus;
+use report_history::StoredReport;
+use summary_report::SummaryPeriod;
+use team_digest::{Delivery, DigestFormat, DigestItem, TeamDigest};
+use taiga_projects::TaigaProjects;
+use project_codes::ProjectCodes;
+use chrono::Timelike;
 
-#[derive(Serialize, Deserialize)]
-struct HolidayResponse {
-    date: String,
-    year: i32,
-    month: i32,
-    day: i32,
-    status: i32,
-}
+/// 任务默认保留天数，超过后归档
+const DEFAULT_TASK_RETENTION_DAYS: i64 = 30;
 
 #[event(start)]
 fn start() {
@@ -28,41 +59,308 @@ fn start() {
 }
 
 #[event(scheduled)]
-async fn scheduled_handler(_event: ScheduledEvent, env: Env, _ctx: ScheduleContext) {
-    console_log!("定时任务触发：检查今日是否为中国法定工作日");
+async fn scheduled_handler(event: ScheduledEvent, env: Env, _ctx: ScheduleContext) {
+    // 定时任务每小时触发一次，按各成员所在时区判断是否到了发送时间
+    let now = chrono::DateTime::from_timestamp_millis(event.schedule() as i64)
+        .unwrap_or_else(chrono::Utc::now);
+
+    // 每天 UTC 0 点归档长期未出现的任务
+    if now.hour() == 0 {
+        archive_stale_tasks(&env, now.date_naive()).await;
+    }
+
+    console_log!("定时任务触发（{}）：检查各成员是否到达发送时间", now.format("%Y-%m-%d %H:%M UTC"));
+
+    let members = match load_team_members(&env).await {
+        Ok(members) => members,
+        Err(e) => {
+            console_log!("⚠️ 读取团队成员失败: {}，使用 GITHUB_TOKEN 对应的用户", e);
+            vec![TeamMember::token_owner()]
+        }
+    };
+
+    // 到达发送时间（或需要重试）且今日需要上班的成员
+    let mut due_members = Vec::new();
     
-    // 先检查今天是否为工作日
-    match is_working_day().await {
-        Ok(true) => {
-            console_log!("✓ 今日为工作日，开始执行每日站会报告生成");
-            match generate_and_send_daily_standup(&env).await {
-                Ok(_) => {
-                    console_log!("✓ 每日站会报告生成并发送成功");
-                }
-                Err(e) => {
-                    console_log!("❌ 每日站会报告生成失败: {}", e.to_string());
+    for member in &members {
+        let today = match member.local_date(now) {
+            Ok(date) => date,
+            Err(e) => {
+                console_log!("⚠️ {} 的时区配置无效: {}，跳过", member.name(), e);
+                continue;
+            }
+        };
+
+        match member.is_send_time(now) {
+            Ok(true) => {}
+            Ok(false) => {
+                // 未到发送时间时，只重试今日失败或中断的运行
+                match needs_retry(&env, member, today, now).await {
+                    Ok(true) => {
+                        console_log!("↻ 重试 {} 今日失败的站会报告", member.name());
+                        due_members.push(member.clone());
+                    }
+                    Ok(false) => {}
+                    Err(e) => {
+                        console_log!("⚠️ 查询 {} 的运行记录失败: {}", member.name(), e);
+                    }
                 }
+                continue;
+            }
+            Err(e) => {
+                console_log!("⚠️ {} 的发送时间配置无效: {}，跳过", member.name(), e);
+                continue;
             }
         }
-        Ok(false) => {
-            console_log!("ℹ️ 今日为非工作日（周末或法定节假日），跳过站会报告生成");
+
+        // 请假的成员不生成报告
+        match is_on_leave(&env, member, today).await {
+            Ok(true) => {
+                console_log!("ℹ️ {} 今日请假，跳过站会报告生成", member.name());
+                continue;
+            }
+            Ok(false) => {}
+            Err(e) => {
+                console_log!("⚠️ 查询 {} 的请假记录失败: {}，按未请假处理", member.name(), e);
+            }
+        }
+
+        // 再检查今天是否为该成员的工作日
+        match is_working_day(&env, member, today).await {
+            Ok(true) => {
+                console_log!("✓ {} 今日为工作日，开始执行每日站会报告生成", member.name());
+                due_members.push(member.clone());
+            }
+            Ok(false) => {
+                console_log!("ℹ️ {} 今日为非工作日（周末或节假日），跳过站会报告生成", member.name());
+            }
+            Err(e) => {
+                console_log!("⚠️ 检查 {} 的工作日状态失败: {}，默认执行站会报告生成", member.name(), e.to_string());
+                due_members.push(member.clone());
+            }
         }
+    }
+
+    // 团队日报模式下各成员只生成并保存报告，由团队日报在统一的发送时间汇总发送
+    let delivery = report_delivery(&env);
+    for member in &due_members {
+        run_daily_standup(&env, member, delivery).await;
+    }
+    if delivery == Delivery::Digest {
+        run_team_digest(&env, &members, now).await;
+    }
+
+    // 每日报告保存之后再发送周报和月报，使汇总包含当天的报告
+    run_due_summaries(&env, &members, now).await;
+}
+
+/// 到达团队日报发送时间后，汇总当天保存的报告发送一条团队日报，结果只记录日志
+async fn run_team_digest(env: &Env, members: &[TeamMember], now: chrono::DateTime<chrono::Utc>) {
+    let (tz, send_time) = match team_digest_schedule(env) {
+        Ok(schedule) => schedule,
         Err(e) => {
-            console_log!("⚠️ 检查工作日状态失败: {}，默认执行站会报告生成", e.to_string());
-            match generate_and_send_daily_standup(&env).await {
-                Ok(_) => {
-                    console_log!("✓ 每日站会报告生成并发送成功");
-                }
-                Err(e) => {
-                    console_log!("❌ 每日站会报告生成失败: {}", e.to_string());
-                }
+            console_log!("⚠️ {}，不发送团队日报", e);
+            return;
+        }
+    };
+    let local_now = now.with_timezone(&tz);
+    if local_now.time() < send_time {
+        return;
+    }
+
+    match send_team_digest(env, members, local_now.date_naive(), false).await {
+        Ok(Some(_)) => console_log!("✓ 团队日报发送成功"),
+        Ok(None) => {}
+        Err(e) => console_log!("❌ 团队日报发送失败: {}", e.to_string()),
+    }
+}
+
+/// 汇总成员在 date 当天保存的站会报告，发送一条团队日报到 TEAM_FEISHU_WEBHOOK_URL（未配置时使用 FEISHU_WEBHOOK_URL）
+/// 运行结果记录在 digest_runs 表中：当天已发送（或失败次数已达上限）时不再发送，force 为 true 时除外；
+/// 没有任何成员的报告时不发送，返回 None
+async fn send_team_digest(env: &Env, members: &[TeamMember], date: chrono::NaiveDate, force: bool) -> Result<Option<String>> {
+    let webhook_url = match env.var("TEAM_FEISHU_WEBHOOK_URL") {
+        Ok(value) if !value.to_string().is_empty() => value.to_string(),
+        _ => env.var("FEISHU_WEBHOOK_URL")?.to_string(),
+    };
+    if webhook_url.is_empty() {
+        return Err(Error::RustError("TEAM_FEISHU_WEBHOOK_URL 和 FEISHU_WEBHOOK_URL 环境变量均未设置".into()));
+    }
+
+    let db = env.d1("DB")?;
+    let db_client = DatabaseClient::new(&db);
+    db_client.migrate().await
+        .map_err(|e| Error::RustError(format!("执行数据库迁移失败: {}", e)))?;
+
+    let digest_date = date.format("%Y-%m-%d").to_string();
+    if !force {
+        let existing_run = db_client.get_digest_run(&digest_date).await
+            .map_err(|e| Error::RustError(e.to_string()))?;
+        if let Some(run) = existing_run {
+            if !run.can_run(chrono::Utc::now()) {
+                return Ok(None);
             }
         }
     }
+
+    // 当天保存的报告和失败的运行；没有运行记录的成员（请假、不上班或尚未到发送时间）不出现在团队日报中
+    let reports = db_client.list_standup_reports(None, &digest_date, &digest_date).await
+        .map_err(|e| Error::RustError(e.to_string()))?;
+    let mut items = Vec::new();
+    for member in members {
+        let login = resolve_login(env, member).await
+            .map_err(|e| Error::RustError(e.to_string()))?;
+        if let Some(stored) = reports.iter().rev().find(|report| report.login == login) {
+            items.push(DigestItem::from_report(member.name(), stored));
+            continue;
+        }
+        let run = db_client.get_standup_run(&login, &digest_date).await
+            .map_err(|e| Error::RustError(e.to_string()))?;
+        if let Some(run) = run.filter(|run| run.status == RunStatus::Failed) {
+            items.push(DigestItem::failed(member.name(), run.error.as_deref().unwrap_or("")));
+        }
+    }
+
+    let locale = report_locale(env);
+    let digest = TeamDigest::new(items, locale);
+    if !digest.has_reports() {
+        console_log!("ℹ️ {} 没有成员的站会报告，不发送团队日报", digest_date);
+        return Ok(None);
+    }
+
+    // 原子地抢占当天的团队日报运行，其他定时任务或手动触发已经开始时跳过
+    let claimed = db_client.start_digest_run(&digest_date, force).await
+        .map_err(|e| Error::RustError(e.to_string()))?;
+    if !claimed {
+        console_log!("ℹ️ {} 的团队日报已发送或正在发送，跳过", digest_date);
+        return Ok(None);
+    }
+
+    console_log!("正在发送团队日报到飞书...");
+    let feishu_webhook = FeishuWebhook::new(webhook_url, locale);
+    let rendered = digest.render_text();
+    let result = feishu_webhook.send_team_digest(&digest, digest_format(env)).await
+        .map_err(|e| format!("团队日报发送失败: {}", e));
+    if let Err(e) = db_client.finish_digest_run(&digest_date, result.as_ref().map(|_| rendered.as_str()).map_err(String::as_str)).await {
+        console_log!("⚠️ {}", e);
+    }
+    result.map_err(Error::RustError)?;
+
+    console_log!("✓ 团队日报已成功发送到飞书");
+    Ok(Some(rendered))
+}
+
+/// 读取团队日报的时区（TEAM_DIGEST_TIMEZONE，默认 Asia/Shanghai）和发送时间（TEAM_DIGEST_TIME，默认 19:00）
+fn team_digest_schedule(env: &Env) -> anyhow::Result<(chrono_tz::Tz, chrono::NaiveTime)> {
+    let timezone = env.var("TEAM_DIGEST_TIMEZONE").map(|v| v.to_string()).unwrap_or_else(|_| "Asia/Shanghai".to_string());
+    let send_time = env.var("TEAM_DIGEST_TIME").map(|v| v.to_string()).unwrap_or_else(|_| "19:00".to_string());
+
+    let tz = timezone.trim().parse::<chrono_tz::Tz>()
+        .map_err(|_| anyhow::anyhow!("无效的 TEAM_DIGEST_TIMEZONE: {}，请使用 IANA 时区名称", timezone))?;
+    let send_time = chrono::NaiveTime::parse_from_str(send_time.trim(), "%H:%M")
+        .map_err(|_| anyhow::anyhow!("无效的 TEAM_DIGEST_TIME: {}，请使用 HH:MM 格式", send_time))?;
+    Ok((tz, send_time))
+}
+
+/// 归档超过 TASK_RETENTION_DAYS 天未出现的任务，结果只记录日志
+async fn archive_stale_tasks(env: &Env, today: chrono::NaiveDate) {
+    let retention_days = task_retention_days(env);
+    if retention_days == 0 {
+        return;
+    }
+
+    let db = match env.d1("DB") {
+        Ok(db) => db,
+        Err(e) => {
+            console_log!("⚠️ 获取数据库失败: {}", e);
+            return;
+        }
+    };
+    let db_client = DatabaseClient::new(&db);
+    if let Err(e) = db_client.migrate().await {
+        console_log!("⚠️ 执行数据库迁移失败: {}", e);
+        return;
+    }
+
+    match db_client.archive_stale_tasks(retention_days, today).await {
+        Ok(0) => {}
+        Ok(count) => console_log!("✓ 已归档 {} 个超过 {} 天未出现的任务", count, retention_days),
+        Err(e) => console_log!("⚠️ 归档旧任务失败: {}", e),
+    }
+}
+
+/// 为单个成员生成并发送（团队日报模式下只生成）站会报告，结果只记录日志
+async fn run_daily_standup(env: &Env, member: &TeamMember, delivery: Delivery) {
+    match generate_and_send_daily_standup(env, member, false, delivery).await {
+        Ok(_) => {
+            console_log!("✓ {} 的每日站会报告生成并发送成功", member.name());
+        }
+        Err(e) => {
+            console_log!("❌ {} 的每日站会报告生成失败: {}", member.name(), e.to_string());
+        }
+    }
+}
+
+/// 在成员本地的周五和每月最后一天发送周报和月报，结果只记录日志
+/// 到达成员的发送时间、且当天的每日报告已经结束（成功，或失败且不再重试）后才发送，
+/// 运行结果记录在 summary_runs 表中，每个周期只发送一次，失败时在之后的定时任务中重试
+async fn run_due_summaries(env: &Env, members: &[TeamMember], now: chrono::DateTime<chrono::Utc>) {
+    for member in members {
+        let today = match member.local_date(now) {
+            Ok(date) => date,
+            Err(_) => continue,
+        };
+        let periods = SummaryPeriod::due_on(today);
+        if periods.is_empty() || !member.is_past_send_time(now).unwrap_or(false) {
+            continue;
+        }
+
+        for period in periods {
+            match run_summary_report(env, member, period, today, now).await {
+                Ok(true) => console_log!("✓ {} 的 {} 汇总报告发送成功", member.name(), period.as_str()),
+                Ok(false) => {}
+                Err(e) => console_log!("❌ {} 的 {} 汇总报告生成失败: {}", member.name(), period.as_str(), e),
+            }
+        }
+    }
+}
+
+/// 需要时生成并发送成员的周报或月报，返回是否已发送
+async fn run_summary_report(env: &Env, member: &TeamMember, period: SummaryPeriod, today: chrono::NaiveDate, now: chrono::DateTime<chrono::Utc>) -> anyhow::Result<bool> {
+    let login = resolve_login(env, member).await?;
+    let period_end = today.format("%Y-%m-%d").to_string();
+
+    let db = env.d1("DB")?;
+    let db_client = DatabaseClient::new(&db);
+    db_client.migrate().await?;
+
+    // 当天的每日报告还在运行或等待重试时，等它结束后再汇总
+    if let Some(run) = db_client.get_standup_run(&login, &period_end).await? {
+        if !run.is_finished(now) {
+            return Ok(false);
+        }
+    }
+    if let Some(run) = db_client.get_summary_run(&login, period.as_str(), &period_end).await? {
+        if !run.can_run(now) {
+            return Ok(false);
+        }
+    }
+
+    // 原子地抢占运行，其他定时任务已经开始时跳过
+    if !db_client.start_summary_run(&login, period.as_str(), &period_end).await? {
+        return Ok(false);
+    }
+    let result = generate_and_send_summary(env, member, period, today).await;
+    let error = result.as_ref().err().map(|e| e.to_string());
+    if let Err(e) = db_client.finish_summary_run(&login, period.as_str(), &period_end, error.as_deref()).await {
+        console_log!("⚠️ {}", e);
+    }
+
+    result.map(|_| true).map_err(|e| anyhow::anyhow!(e.to_string()))
 }
 
 #[event(fetch)]
-async fn fetch_handler(req: Request, env: Env, _ctx: Context) -> Result<Response> {
+async fn fetch_handler(mut req: Request, env: Env, _ctx: Context) -> Result<Response> {
     let url = req.url()?;
     
     match url.path() {
@@ -70,16 +368,135 @@ async fn fetch_handler(req: Request, env: Env, _ctx: Context) -> Result<Response
             Response::ok("服务运行正常")
         }
         "/manual-trigger" => {
-            // 手动触发站会报告生成
-            match generate_and_send_daily_standup(&env).await {
-                Ok(report) => {
-                    let response = serde_json::json!({
+            // 手动触发站会报告生成，?user= 指定成员，否则为全部成员生成
+            // 今日已发送的报告不会重复发送，?force=true 时重新生成并发送
+            // 团队日报模式下未指定成员时，为全部成员生成后汇总发送一条团队日报
+            let force = query_param(&url, "force").map(|value| value == "true").unwrap_or(false);
+            // 强制重新发送会产生重复消息，需要 ADMIN_TOKEN
+            if force && !admin_api::is_authorized(&req, &env) {
+                return Response::error("Unauthorized", 401);
+            }
+            let user = query_param(&url, "user");
+            let members = match &user {
+                Some(login) => vec![resolve_member(&env, login).await?],
+                None => load_team_members(&env).await
+                    .map_err(|e| Error::RustError(format!("读取团队成员失败: {}", e)))?,
+            };
+
+            if user.is_none() && report_delivery(&env) == Delivery::Digest {
+                for member in &members {
+                    if let Err(e) = generate_and_send_daily_standup(&env, member, force, Delivery::Digest).await {
+                        console_log!("❌ {} 的站会报告生成失败: {}", member.name(), e.to_string());
+                    }
+                }
+                let today = match team_digest_schedule(&env) {
+                    Ok((tz, _)) => chrono::Utc::now().with_timezone(&tz).date_naive(),
+                    Err(e) => return Response::error(e.to_string(), 500),
+                };
+                let response = match send_team_digest(&env, &members, today, force).await {
+                    Ok(Some(digest)) => serde_json::json!({
+                        "success": true,
+                        "message": "团队日报生成并发送成功",
+                        "report": digest
+                    }),
+                    Ok(None) => serde_json::json!({
                         "success": true,
-                        "message": "每日站会报告生成并发送成功",
+                        "message": "团队日报今日已发送或没有可汇总的报告，未重复发送"
+                    }),
+                    Err(e) => {
+                        let response = serde_json::json!({
+                            "success": false,
+                            "error": e.to_string()
+                        });
+                        return Ok(Response::from_json(&response)?.with_status(500));
+                    }
+                };
+                return Response::from_json(&response);
+            }
+
+            let mut reports = Vec::new();
+            for member in &members {
+                match generate_and_send_daily_standup(&env, member, force, Delivery::Individual).await {
+                    Ok(stored) => reports.push(serde_json::json!({
+                        "user": member.login,
+                        "report": stored.report
+                    })),
+                    Err(e) => {
+                        let response = serde_json::json!({
+                            "success": false,
+                            "user": member.login,
+                            "error": e.to_string()
+                        });
+                        return Ok(Response::from_json(&response)?.with_status(500));
+                    }
+                }
+            }
+
+            let response = serde_json::json!({
+                "success": true,
+                "message": "每日站会报告生成并发送成功",
+                "report": reports.first().map(|r| r["report"].clone()),
+                "reports": reports
+            });
+            Response::from_json(&response)
+        }
+        "/manual-summary" => {
+            // 手动生成并发送周报或月报，?period=weekly|monthly，?user= 指定成员，否则为全部成员生成
+            if !admin_api::is_authorized(&req, &env) {
+                return Response::error("Unauthorized", 401);
+            }
+            let period = match query_param(&url, "period").as_deref().map(SummaryPeriod::parse) {
+                Some(Some(period)) => period,
+                Some(None) => return Response::error("period 参数无效，可选 weekly 或 monthly", 400),
+                None => SummaryPeriod::Weekly,
+            };
+            let members = match query_param(&url, "user") {
+                Some(login) => vec![resolve_member(&env, &login).await?],
+                None => load_team_members(&env).await
+                    .map_err(|e| Error::RustError(format!("读取团队成员失败: {}", e)))?,
+            };
+
+            let mut reports = Vec::new();
+            for member in &members {
+                let today = member.local_date(chrono::Utc::now())
+                    .map_err(|e| Error::RustError(e.to_string()))?;
+                match generate_and_send_summary(&env, member, period, today).await {
+                    Ok(report) => reports.push(serde_json::json!({
+                        "user": member.login,
                         "report": report
-                    });
-                    Response::from_json(&response)
+                    })),
+                    Err(e) => {
+                        let response = serde_json::json!({
+                            "success": false,
+                            "user": member.login,
+                            "error": e.to_string()
+                        });
+                        return Ok(Response::from_json(&response)?.with_status(500));
+                    }
                 }
+            }
+
+            let response = serde_json::json!({
+                "success": true,
+                "period": period.as_str(),
+                "reports": reports
+            });
+            Response::from_json(&response)
+        }
+        "/preview" => {
+            // 流式预览页面，不发送到飞书；页面本身不生成报告，由页面带着 ADMIN_TOKEN 请求 /preview/stream
+            Response::from_html(include_str!("preview.html"))
+        }
+        "/preview/stream" => {
+            if !admin_api::is_authorized(&req, &env) {
+                return Response::error("Unauthorized", 401);
+            }
+            let member = match query_param(&url, "user") {
+                Some(login) => resolve_member(&env, &login).await?,
+                None => TeamMember::token_owner(),
+            };
+            match stream_standup_preview(&env, &member).await {
+                Ok(response) => Ok(response),
                 Err(e) => {
                     let response = serde_json::json!({
                         "success": false,
@@ -90,8 +507,14 @@ async fn fetch_handler(req: Request, env: Env, _ctx: Context) -> Result<Response
             }
         }
         "/check-working-day" => {
-            // 检查今天是否为工作日
-            match is_working_day().await {
+            // 检查今天是否为工作日，?user= 指定成员（使用该成员的日历）
+            let member = match query_param(&url, "user") {
+                Some(login) => resolve_member(&env, &login).await?,
+                None => TeamMember::token_owner(),
+            };
+            let today = member.local_date(chrono::Utc::now())
+                .map_err(|e| Error::RustError(e.to_string()))?;
+            match is_working_day(&env, &member, today).await {
                 Ok(is_working) => {
                     let response = serde_json::json!({
                         "success": true,
@@ -109,13 +532,170 @@ async fn fetch_handler(req: Request, env: Env, _ctx: Context) -> Result<Response
                 }
             }
         }
+        "/ai-usage" => {
+            // 按用户汇总月度 AI 用量和成本，month 参数格式为 YYYY-MM，默认当月（需要 ADMIN_TOKEN）
+            if !admin_api::is_authorized(&req, &env) {
+                return Response::error("Unauthorized", 401);
+            }
+            let month = url.query_pairs()
+                .find(|(key, _)| key == "month")
+                .map(|(_, value)| value.to_string())
+                .unwrap_or_else(|| chrono::Utc::now().format("%Y-%m").to_string());
+
+            if chrono::NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d").is_err() {
+                return Response::error("month 参数格式无效，请使用 YYYY-MM 格式", 400);
+            }
+
+            let db = env.d1("DB")?;
+            let db_client = DatabaseClient::new(&db);
+            match db_client.get_monthly_ai_usage(&month).await {
+                Ok(users) => {
+                    let response = serde_json::json!({
+                        "success": true,
+                        "month": month,
+                        "users": users
+                    });
+                    Response::from_json(&response)
+                }
+                Err(e) => {
+                    let response = serde_json::json!({
+                        "success": false,
+                        "error": e.to_string()
+                    });
+                    Ok(Response::from_json(&response)?.with_status(500))
+                }
+            }
+        }
+        "/holiday-calendar" => {
+            admin_api::handle_holiday_calendar(&mut req, &env, &url).await
+        }
+        "/team-members" => {
+            admin_api::handle_team_members(&mut req, &env, &url).await
+        }
+        "/calendars/ics" => {
+            admin_api::handle_ics_calendars(&mut req, &env, &url).await
+        }
+        "/reports" => {
+            admin_api::handle_reports(&req, &env, &url).await
+        }
+        "/leave" => {
+            admin_api::handle_leave(&mut req, &env, &url).await
+        }
+        "/leave/ics" => {
+            admin_api::handle_leave_ics(&mut req, &env, &url).await
+        }
+        "/tasks" => {
+            admin_api::handle_tasks(&mut req, &env, &url).await
+        }
+        "/tasks/merge" => {
+            admin_api::handle_task_merge(&mut req, &env).await
+        }
+        "/project-codes" => {
+            admin_api::handle_project_codes(&mut req, &env, &url).await
+        }
+        "/admin/export" => {
+            admin_api::handle_export(&req, &env, &url).await
+        }
+        "/admin/import" => {
+            admin_api::handle_import(&mut req, &env, &url).await
+        }
         _ => {
             Response::error("Not found", 404)
         }
     }
 }
 
-async fn generate_and_send_daily_standup(env: &Env) -> Result<String> {
+/// 读取团队成员，未配置成员时使用 GITHUB_TOKEN 对应的用户
+async fn load_team_members(env: &Env) -> anyhow::Result<Vec<TeamMember>> {
+    let db = env.d1("DB")?;
+    let db_client = DatabaseClient::new(&db);
+    db_client.migrate().await?;
+
+    let members = db_client.list_team_members().await?;
+    if members.is_empty() {
+        Ok(vec![TeamMember::token_owner()])
+    } else {
+        Ok(members)
+    }
+}
+
+/// 按 GitHub 用户名查找团队成员
+async fn resolve_member(env: &Env, login: &str) -> Result<TeamMember> {
+    let db = env.d1("DB")?;
+    let db_client = DatabaseClient::new(&db);
+    db_client.migrate().await
+        .map_err(|e| Error::RustError(format!("执行数据库迁移失败: {}", e)))?;
+
+    db_client.get_team_member(login).await
+        .map_err(|e| Error::RustError(e.to_string()))?
+        .ok_or_else(|| Error::RustError(format!("未找到团队成员: {}", login)))
+}
+
+/// 生成并发送成员今日的站会报告，运行结果记录在 standup_runs 表中
+/// 今日已发送成功或正在生成时直接返回，force 为 true 时重新生成并发送；
+/// 团队日报模式下只生成报告，由团队日报统一发送
+async fn generate_and_send_daily_standup(env: &Env, member: &TeamMember, force: bool, delivery: Delivery) -> Result<StoredReport> {
+    let github_token = env.var("GITHUB_TOKEN")?.to_string();
+    if github_token.is_empty() {
+        return Err(Error::RustError("GITHUB_TOKEN 环境变量未设置".into()));
+    }
+
+    let db = env.d1("DB")?;
+    let db_client = DatabaseClient::new(&db);
+    
+    console_log!("检查数据库迁移...");
+    db_client.migrate().await
+        .map_err(|e| Error::RustError(format!("执行数据库迁移失败: {}", e)))?;
+
+    let github_client = GitHubApiClient::new(github_token, report_locale(env));
+    let login = member_login(&github_client, member).await.unwrap_or_else(|e| {
+        console_log!("⚠️ 获取 GitHub 用户失败: {}", e);
+        member.login.clone()
+    });
+    let today = member.local_date(chrono::Utc::now())
+        .map_err(|e| Error::RustError(e.to_string()))?;
+    let run_date = today.format("%Y-%m-%d").to_string();
+
+    // 原子地抢占当天的运行，抢占失败说明已生成或正在生成中
+    let claimed = db_client.start_standup_run(&login, &run_date, force).await
+        .map_err(|e| Error::RustError(e.to_string()))?;
+    if !claimed {
+        let existing_run = db_client.get_standup_run(&login, &run_date).await
+            .map_err(|e| Error::RustError(e.to_string()))?;
+        if let Some(run) = existing_run.filter(|run| run.status == RunStatus::Succeeded) {
+            console_log!("ℹ️ {} 的 {} 站会报告已生成，跳过", member.name(), run_date);
+            let stored = db_client.list_standup_reports(Some(&login), &run_date, &run_date).await
+                .map_err(|e| Error::RustError(e.to_string()))?
+                .pop();
+            return Ok(stored.unwrap_or_else(|| {
+                StoredReport::new(&login, &run_date, run.report.as_deref().unwrap_or(""), "", &[])
+            }));
+        }
+        return Err(Error::RustError(format!("{} 的 {} 站会报告正在生成中", member.name(), run_date)));
+    }
+
+    match send_daily_standup(env, member, &db_client, &login, today, delivery).await {
+        Ok(stored) => {
+            let channel = match delivery {
+                Delivery::Individual => "feishu",
+                Delivery::Digest => "digest",
+            };
+            if let Err(e) = db_client.complete_standup_run(&login, &run_date, &stored.report, &[channel]).await {
+                console_log!("⚠️ 记录站会运行结果失败: {}", e);
+            }
+            Ok(stored)
+        }
+        Err(e) => {
+            if let Err(record_error) = db_client.fail_standup_run(&login, &run_date, &e.to_string()).await {
+                console_log!("⚠️ 记录站会运行失败原因失败: {}", record_error);
+            }
+            Err(e)
+        }
+    }
+}
+
+/// 获取 PR、生成报告，单独发送模式下发送到飞书
+async fn send_daily_standup(env: &Env, member: &TeamMember, db_client: &DatabaseClient<'_>, login: &str, today: chrono::NaiveDate, delivery: Delivery) -> Result<StoredReport> {
     // 获取环境变量
     let github_token = env.var("GITHUB_TOKEN")?.to_string();
     let openai_api_key = env.var("OPENAI_API_KEY")?.to_string();
@@ -123,137 +703,577 @@ async fn generate_and_send_daily_standup(env: &Env) -> Result<String> {
     let openai_model = env.var("OPENAI_MODEL").map(|s| s.to_string()).unwrap_or_else(|_| "gpt-3.5-turbo".to_string());
     let feishu_webhook_url = env.var("FEISHU_WEBHOOK_URL")?.to_string();
 
-    if github_token.is_empty() {
-        return Err(Error::RustError("GITHUB_TOKEN 环境变量未设置".into()));
-    }
-
-    if feishu_webhook_url.is_empty() {
+    if delivery == Delivery::Individual && feishu_webhook_url.is_empty() {
         return Err(Error::RustError("FEISHU_WEBHOOK_URL 环境变量未设置".into()));
     }
 
-    // 初始化数据库
-    let db = env.d1("DB")?;
-    let db_client = DatabaseClient::new(&db);
-    
-    console_log!("初始化数据库表...");
-    db_client.init_tables().await
-        .map_err(|e| Error::RustError(format!("初始化数据库表失败: {}", e)))?;
-
-    console_log!("开始获取今日 GitHub PR 数据...");
+    console_log!("开始获取 {} 今日 GitHub PR 数据...", member.name());
 
     // 创建 GitHub API 客户端
-    let github_client = GitHubApiClient::new(github_token);
+    let locale = report_locale(env);
+    let github_client = GitHubApiClient::new(github_token, locale)
+        .with_taiga_projects(taiga_projects(env))
+        .with_project_codes(project_codes(db_client).await);
 
     // 获取今天的 PR
-    let pr_response = github_client.get_today_prs().await
+    let tz = member.tz()
+        .map_err(|e| Error::RustError(e.to_string()))?;
+    let pr_response = github_client.get_prs_by_date(today, tz, &member.login).await
         .map_err(|e| Error::RustError(format!("获取 GitHub PR 失败: {}", e)))?;
 
     console_log!("✓ 成功获取 {} 个 PR", pr_response.total_count);
 
     // 生成站会报告数据
-    let standup_data = github_client.generate_standup_report(&pr_response, Some(&db_client)).await;
+    let calendar = member_calendar(env, db_client, member).await;
+    let day_context = TaskDayContext { login, calendar: calendar.as_ref(), mode: task_days_mode(env), today };
+    let standup_data = github_client.generate_standup_report(&pr_response, &day_context, Some(db_client)).await
+        .map_err(|e| Error::RustError(format!("生成站会报告数据失败: {}", e)))?;
+    let raw_data = standup_data.report.clone();
+    let entries = standup_data.entries.clone();
 
     let final_report = if !openai_api_key.is_empty() {
         console_log!("正在使用 AI 生成格式化的站会报告...");
         
         // 创建 AI 客户端
-        let ai_client = AIClient::new(openai_api_key, openai_base_url, openai_model);
+        let ai_client = AIClient::new(openai_api_key, openai_base_url, openai_model, locale);
         
-        // 使用 AI 生成最终报告
-        match ai_client.generate_standup_report(&standup_data).await {
-            Ok(report) => {
-                console_log!("✓ AI 报告生成成功");
-                report
+        // 发送给 AI 之前脱敏
+        match create_redactor(env, &pr_response, &standup_data.entries) {
+            Ok(mut redactor) => {
+                let mut redacted_data = redactor.redact(&standup_data.report);
+                if redactor.has_placeholders() {
+                    redacted_data.push_str(locale.texts().placeholder_notice);
+                }
+
+                // 使用 AI 生成最终报告，并校验天数和任务编号
+                let context = ReportContext {
+                    locale,
+                    login,
+                    report_date: today,
+                    redactor: &mut redactor,
+                    redacted_data: &redacted_data,
+                    max_retries: validation_retries(env),
+                };
+                match generate_validated_report(env, &ai_client, db_client, context, &standup_data).await {
+                    Ok(report) => report,
+                    Err(e) => {
+                        console_log!("⚠️ AI 生成失败，使用原始数据: {}", e);
+                        standup_data.report
+                    }
+                }
             }
             Err(e) => {
-                console_log!("⚠️ AI 生成失败，使用原始数据: {}", e);
-                standup_data
+                console_log!("⚠️ 脱敏配置无效，不调用 AI，使用原始数据: {}", e);
+                standup_data.report
             }
         }
     } else {
         console_log!("⚠️ 未配置 OpenAI API，使用原始数据");
-        standup_data
+        standup_data.report
     };
 
-    // 发送到飞书
-    console_log!("正在发送报告到飞书...");
-    let feishu_webhook = FeishuWebhook::new(feishu_webhook_url);
-    
-    feishu_webhook.send_standup_report(&final_report).await
-        .map_err(|e| Error::RustError(format!("飞书消息发送失败: {}", e)))?;
+    if delivery == Delivery::Individual {
+        // 发送到飞书
+        console_log!("正在发送报告到飞书...");
+        let feishu_webhook = FeishuWebhook::new(feishu_webhook_url, locale);
+        
+        // 配置了团队成员时，在标题中标注成员名字
+        let member_name = if member.is_token_owner() { None } else { Some(member.name()) };
+        feishu_webhook.send_standup_report(&final_report, member_name).await
+            .map_err(|e| Error::RustError(format!("飞书消息发送失败: {}", e)))?;
+
+        console_log!("✓ 报告已成功发送到飞书");
+    }
+
+    // 保存报告历史，失败时只记录日志
+    let stored_report = StoredReport::new(login, &today.format("%Y-%m-%d").to_string(), &final_report, &raw_data, &entries);
+    if let Err(e) = db_client.save_standup_report(&stored_report).await {
+        console_log!("⚠️ 保存站会报告失败: {}", e);
+    }
+
+    Ok(stored_report)
+}
+
+/// 汇总成员本周或本月的每日报告、已合并的 PR 和任务天数，生成周报或月报并发送到飞书
+async fn generate_and_send_summary(env: &Env, member: &TeamMember, period: SummaryPeriod, date: chrono::NaiveDate) -> Result<String> {
+    let github_token = env.var("GITHUB_TOKEN")?.to_string();
+    let openai_api_key = env.var("OPENAI_API_KEY").map(|s| s.to_string()).unwrap_or_default();
+    let openai_base_url = env.var("OPENAI_BASE_URL").map(|s| s.to_string()).unwrap_or_else(|_| "https://api.openai.com/v1".to_string());
+    let openai_model = env.var("OPENAI_MODEL").map(|s| s.to_string()).unwrap_or_else(|_| "gpt-3.5-turbo".to_string());
+    let feishu_webhook_url = env.var("FEISHU_WEBHOOK_URL")?.to_string();
+
+    if feishu_webhook_url.is_empty() {
+        return Err(Error::RustError("FEISHU_WEBHOOK_URL 环境变量未设置".into()));
+    }
+
+    let db = env.d1("DB")?;
+    let db_client = DatabaseClient::new(&db);
+    db_client.migrate().await
+        .map_err(|e| Error::RustError(format!("执行数据库迁移失败: {}", e)))?;
+
+    let locale = report_locale(env);
+    let github_client = GitHubApiClient::new(github_token, locale);
+    let login = member_login(&github_client, member).await
+        .map_err(|e| Error::RustError(format!("获取 GitHub 用户失败: {}", e)))?;
+
+    // 读取时间段内的每日报告和任务天数
+    let (from, to) = period.range(date);
+    let from_str = from.format("%Y-%m-%d").to_string();
+    let to_str = to.format("%Y-%m-%d").to_string();
+    let reports = db_client.list_standup_reports(Some(&login), &from_str, &to_str).await
+        .map_err(|e| Error::RustError(e.to_string()))?;
+    if reports.is_empty() {
+        return Err(Error::RustError(format!("{} ~ {} 没有站会报告，跳过汇总", from_str, to_str)));
+    }
+    let tasks = db_client.get_task_records(&summary_report::task_keys(&reports)).await
+        .map_err(|e| Error::RustError(e.to_string()))?;
+
+    let summary_data = summary_report::build_summary_data(period, from, to, &reports, &tasks, locale);
+    console_log!("✓ 汇总 {} 份每日报告、{} 个任务", reports.len(), tasks.len());
+
+    let final_report = if !openai_api_key.is_empty() {
+        console_log!("正在使用 AI 生成{}...", period.title(locale));
+        let ai_client = AIClient::new(openai_api_key, openai_base_url, openai_model, locale);
+
+        let rules = match env.var("REDACTION_RULES") {
+            Ok(rules) => RedactionRules::from_json(&rules.to_string()),
+            Err(_) => Ok(RedactionRules::default()),
+        };
+        match rules.and_then(|rules| Redactor::from_sources(&rules, &summary_data.repos, &summary_data.codes, &summary_data.texts)) {
+            Ok(mut redactor) => {
+                let mut redacted_data = redactor.redact(&summary_data.report);
+                if redactor.has_placeholders() {
+                    redacted_data.push_str(locale.texts().placeholder_notice);
+                }
+
+                match ai_client.generate_summary_report(&redacted_data).await {
+                    Ok(completion) => {
+                        record_ai_usage(env, &db_client, &login, date, &completion).await;
+                        redactor.restore(&completion.content)
+                    }
+                    Err(e) => {
+                        console_log!("⚠️ AI 生成失败，使用原始数据: {}", e);
+                        summary_data.report
+                    }
+                }
+            }
+            Err(e) => {
+                console_log!("⚠️ 脱敏配置无效，不调用 AI，使用原始数据: {}", e);
+                summary_data.report
+            }
+        }
+    } else {
+        console_log!("⚠️ 未配置 OpenAI API，使用原始数据");
+        summary_data.report
+    };
 
-    console_log!("✓ 报告已成功发送到飞书");
+    let feishu_webhook = FeishuWebhook::new(feishu_webhook_url, locale);
+    let member_name = if member.is_token_owner() { None } else { Some(member.name()) };
+    feishu_webhook.send_summary_report(period.title(locale), &final_report, member_name).await
+        .map_err(|e| Error::RustError(format!("飞书消息发送失败: {}", e)))?;
 
     Ok(final_report)
 }
 
-/// 检查今天是否为中国法定工作日
-/// 返回 true 表示工作日（status = 0 或 2），false 表示非工作日（status = 1 或 3）
-async fn is_working_day() -> Result<bool> {
-    // 获取当前 UTC 时间
-    let now = js_sys::Date::new_0();
-    
-    // 手动计算北京时间（UTC+8）
-    let utc_timestamp = now.get_time(); // 毫秒时间戳
-    let beijing_timestamp = utc_timestamp + (8.0 * 60.0 * 60.0 * 1000.0); // 加8小时
-    let beijing_time = js_sys::Date::new(&JsValue::from_f64(beijing_timestamp));
-    
-    let year = beijing_time.get_full_year() as i32;
-    let month = (beijing_time.get_month() + 1) as i32; // JavaScript月份从0开始
-    let day = beijing_time.get_date() as i32;
-    
-    let date_str = format!("{:04}-{:02}-{:02}", year, month, day);
-    let api_url = format!("http://api.haoshenqi.top/holiday?date={}", date_str);
-    
-    console_log!("正在查询日期 {} 的工作日状态...", date_str);
-    
-    // 发起HTTP请求
-    let mut init = RequestInit::new();
-    init.with_method(Method::Get);
-    
-    let request = Request::new_with_init(&api_url, &init)?;
-    let mut response = Fetch::Request(request).send().await?;
-    
-    if response.status_code() != 200 {
-        return Err(Error::RustError(format!("节假日API请求失败，状态码: {}", response.status_code())));
+/// 流式生成站会报告预览，以 SSE 事件将 AI 输出逐段转发给浏览器
+/// 事件类型：token（增量文本）、report（按原始数据校验修正后的完整报告，替换已显示的内容）、
+/// error（错误信息）、done（生成结束）
+async fn stream_standup_preview(env: &Env, member: &TeamMember) -> Result<Response> {
+    let github_token = env.var("GITHUB_TOKEN")?.to_string();
+    let openai_api_key = env.var("OPENAI_API_KEY")?.to_string();
+    let openai_base_url = env.var("OPENAI_BASE_URL").map(|s| s.to_string()).unwrap_or_else(|_| "https://api.openai.com/v1".to_string());
+    let openai_model = env.var("OPENAI_MODEL").map(|s| s.to_string()).unwrap_or_else(|_| "gpt-3.5-turbo".to_string());
+
+    if github_token.is_empty() {
+        return Err(Error::RustError("GITHUB_TOKEN 环境变量未设置".into()));
     }
-    
-    // 先获取原始文本响应进行调试
-    let response_text = response.text().await?;
-    console_log!("节假日API原始响应: {}", response_text);
-    
-    // 尝试解析 JSON 数组
-    let holiday_responses: Vec<HolidayResponse> = serde_json::from_str(&response_text)
-        .map_err(|e| Error::RustError(format!("解析节假日API响应失败: {}", e)))?;
-    
-    // 获取第一个（也是唯一的）响应
-    let holiday_response = holiday_responses.into_iter().next()
-        .ok_or_else(|| Error::RustError("节假日API响应为空数组".into()))?;
-    
-    console_log!("节假日API响应: 日期={}, 状态={}", holiday_response.date, holiday_response.status);
-    
-    // status: 0普通工作日, 1周末双休日, 2需要补班的工作日, 3法定节假日
-    // 只有 0 和 2 才是工作日
-    match holiday_response.status {
-        0 => {
-            console_log!("✓ 今日为普通工作日");
-            Ok(true)
+
+    if openai_api_key.is_empty() {
+        return Err(Error::RustError("OPENAI_API_KEY 环境变量未设置，无法预览 AI 报告".into()));
+    }
+
+    let db = env.d1("DB")?;
+    let db_client = DatabaseClient::new(&db);
+    db_client.migrate().await
+        .map_err(|e| Error::RustError(format!("执行数据库迁移失败: {}", e)))?;
+
+    let locale = report_locale(env);
+    let github_client = GitHubApiClient::new(github_token, locale)
+        .with_taiga_projects(taiga_projects(env))
+        .with_project_codes(project_codes(&db_client).await);
+    let today = member.local_date(chrono::Utc::now())
+        .map_err(|e| Error::RustError(e.to_string()))?;
+    let tz = member.tz()
+        .map_err(|e| Error::RustError(e.to_string()))?;
+    let pr_response = github_client.get_prs_by_date(today, tz, &member.login).await
+        .map_err(|e| Error::RustError(format!("获取 GitHub PR 失败: {}", e)))?;
+    let login = member_login(&github_client, member).await.unwrap_or_else(|e| {
+        console_log!("⚠️ 获取 GitHub 用户失败: {}", e);
+        member.login.clone()
+    });
+    let calendar = member_calendar(env, &db_client, member).await;
+    let day_context = TaskDayContext { login: &login, calendar: calendar.as_ref(), mode: task_days_mode(env), today };
+    let standup_data = github_client.generate_standup_report(&pr_response, &day_context, Some(&db_client)).await
+        .map_err(|e| Error::RustError(format!("生成站会报告数据失败: {}", e)))?;
+
+    let mut redactor = create_redactor(env, &pr_response, &standup_data.entries)
+        .map_err(|e| Error::RustError(format!("脱敏配置无效: {}", e)))?;
+    let mut redacted_data = redactor.redact(&standup_data.report);
+    if redactor.has_placeholders() {
+        redacted_data.push_str(locale.texts().placeholder_notice);
+    }
+
+    let ai_client = AIClient::new(openai_api_key, openai_base_url, openai_model, locale);
+    let tokens = ai_client.stream_standup_report(&redacted_data).await
+        .map_err(|e| Error::RustError(format!("AI 流式生成失败: {}", e)))?;
+
+    let env = env.clone();
+    let validator = ReportValidator::new(&standup_data);
+    let mut restorer = StreamingRestorer::new(redactor);
+    let events = tokens
+        .map(Some)
+        .chain(futures::stream::once(async { None }))
+        .then(move |item| {
+            let (event, completion) = match item {
+                Some(Ok(StreamChunk::Token(token))) => (sse_event("token", &restorer.push(&token)), None),
+                Some(Ok(StreamChunk::Done(completion))) => {
+                    // 逐段输出无法重新生成，只按原始数据修正天数并移除编造的编号
+                    let result = validator.validate(&restorer.restore_full(&completion.content));
+                    for discrepancy in &result.discrepancies {
+                        console_log!("⚠️ AI 预览与原始数据不一致: {}", discrepancy.describe(locale));
+                    }
+                    (sse_event("report", &result.corrected), Some(completion))
+                }
+                Some(Err(e)) => (sse_event("error", &e.to_string()), None),
+                None => (format!("{}{}", sse_event("token", &restorer.finish()), sse_event("done", "")), None),
+            };
+            let env = env.clone();
+            let login = login.clone();
+            async move {
+                // 流结束时记录本次预览的 AI 用量
+                if let Some(completion) = completion {
+                    match env.d1("DB") {
+                        Ok(db) => record_ai_usage(&env, &DatabaseClient::new(&db), &login, today, &completion).await,
+                        Err(e) => console_log!("⚠️ 记录 AI 用量失败: {}", e),
+                    }
+                }
+                Ok::<_, Error>(event.into_bytes())
+            }
+        });
+
+    let mut response = Response::from_stream(events)?;
+    response.headers_mut().set("Content-Type", "text/event-stream; charset=utf-8")?;
+    response.headers_mut().set("Cache-Control", "no-cache")?;
+    Ok(response)
+}
+
+/// 格式化一条 SSE 事件，data 使用 JSON 字符串编码以保留换行
+fn sse_event(event: &str, data: &str) -> String {
+    if event == "token" && data.is_empty() {
+        return String::new();
+    }
+    format!("event: {}\ndata: {}\n\n", event, serde_json::to_string(data).unwrap_or_default())
+}
+
+/// 生成一份 AI 报告的上下文
+struct ReportContext<'a> {
+    locale: Locale,
+    /// 成员的 GitHub 用户名和本地报告日期，用于记录 AI 用量
+    login: &'a str,
+    report_date: chrono::NaiveDate,
+    /// 用于还原 AI 输出和脱敏纠错说明
+    redactor: &'a mut Redactor,
+    /// 已脱敏的站会数据
+    redacted_data: &'a str,
+    /// 出现编造的任务编号时最多重新生成的次数
+    max_retries: u32,
+}
+
+/// 读取 AI_VALIDATION_RETRIES 环境变量，默认重试 1 次
+fn validation_retries(env: &Env) -> u32 {
+    env.var("AI_VALIDATION_RETRIES")
+        .ok()
+        .and_then(|v| v.to_string().parse().ok())
+        .unwrap_or(1)
+}
+
+/// 调用 AI 生成报告，并用原始数据校验输出
+/// 出现编造的任务编号时带着纠错说明重新生成（最多 max_retries 次），
+/// 天数不一致则直接按原始数据修正
+async fn generate_validated_report(
+    env: &Env,
+    ai_client: &AIClient,
+    db_client: &DatabaseClient<'_>,
+    context: ReportContext<'_>,
+    standup_data: &github_api::StandupData,
+) -> anyhow::Result<String> {
+    let ReportContext { locale, login, report_date, redactor, redacted_data, max_retries } = context;
+    let validator = ReportValidator::new(standup_data);
+
+    let mut prompt = redacted_data.to_string();
+    let mut attempt = 0;
+    loop {
+        let completion = ai_client.generate_standup_report(&prompt).await?;
+        console_log!("✓ AI 报告生成成功（{} tokens，耗时 {}ms）",
+            completion.usage.total_tokens, completion.latency_ms);
+        record_ai_usage(env, db_client, login, report_date, &completion).await;
+
+        let report = redactor.restore(&completion.content);
+        let result = validator.validate(&report);
+        if result.is_valid() {
+            return Ok(report);
         }
-        1 => {
-            console_log!("ℹ️ 今日为周末双休日");
-            Ok(false)
+
+        for discrepancy in &result.discrepancies {
+            console_log!("⚠️ AI 报告与原始数据不一致: {}", discrepancy.describe(locale));
         }
-        2 => {
-            console_log!("✓ 今日为需要补班的工作日");
-            Ok(true)
+
+        if result.needs_regeneration() && attempt < max_retries {
+            attempt += 1;
+            console_log!("正在重新生成 AI 报告（第 {} 次重试）...", attempt);
+            // 纠错说明中包含 AI 输出的原文，同样需要脱敏
+            let feedback = redactor.redact(&validator.feedback_prompt(&result.discrepancies, locale));
+            prompt = format!("{}{}", redacted_data, feedback);
+            continue;
         }
-        3 => {
-            console_log!("ℹ️ 今日为法定节假日");
-            Ok(false)
+
+        console_log!("已按原始数据修正 {} 处不一致", result.discrepancies.len());
+        return Ok(result.corrected);
+    }
+}
+
+/// 读取 D1 中的项目代号规则（仓库到项目代号的映射），读取失败或规则无效时项目代号使用仓库名
+async fn project_codes(db_client: &DatabaseClient<'_>) -> ProjectCodes {
+    let rules = db_client.list_project_code_rules().await
+        .and_then(|rules| ProjectCodes::new(&rules));
+    rules.unwrap_or_else(|e| {
+        console_log!("⚠️ 读取项目代号规则失败，使用仓库名: {}", e);
+        ProjectCodes::default()
+    })
+}
+
+/// 根据 TAIGA_PROJECTS 环境变量读取仓库到 Taiga 项目的映射，未配置或无效时不识别 #N 引用
+fn taiga_projects(env: &Env) -> TaigaProjects {
+    match env.var("TAIGA_PROJECTS") {
+        Ok(value) => TaigaProjects::from_json(&value.to_string()).unwrap_or_else(|e| {
+            console_log!("⚠️ {}，不识别 #N 形式的 Taiga 任务引用", e);
+            TaigaProjects::default()
+        }),
+        Err(_) => TaigaProjects::default(),
+    }
+}
+
+/// 读取 REPORT_LOCALE 环境变量，未配置或无法识别时使用中文
+fn report_locale(env: &Env) -> Locale {
+    match env.var("REPORT_LOCALE") {
+        Ok(value) => {
+            let value = value.to_string();
+            Locale::parse(&value).unwrap_or_else(|| {
+                console_log!("⚠️ 无法识别的 REPORT_LOCALE: {}，使用中文", value);
+                Locale::default()
+            })
         }
-        _ => {
-            console_log!("⚠️ 未知的工作日状态: {}", holiday_response.status);
-            Ok(false)
+        Err(_) => Locale::default(),
+    }
+}
+
+/// 读取 REPORT_DELIVERY 环境变量，未配置或无法识别时每个成员单独发送
+fn report_delivery(env: &Env) -> Delivery {
+    match env.var("REPORT_DELIVERY") {
+        Ok(value) => {
+            let value = value.to_string();
+            Delivery::parse(&value).unwrap_or_else(|| {
+                console_log!("⚠️ 无法识别的 REPORT_DELIVERY: {}，每个成员单独发送", value);
+                Delivery::default()
+            })
+        }
+        Err(_) => Delivery::default(),
+    }
+}
+
+/// 读取 TEAM_DIGEST_FORMAT 环境变量，未配置或无法识别时使用纯文本
+fn digest_format(env: &Env) -> DigestFormat {
+    match env.var("TEAM_DIGEST_FORMAT") {
+        Ok(value) => {
+            let value = value.to_string();
+            DigestFormat::parse(&value).unwrap_or_else(|| {
+                console_log!("⚠️ 无法识别的 TEAM_DIGEST_FORMAT: {}，使用纯文本", value);
+                DigestFormat::default()
+            })
+        }
+        Err(_) => DigestFormat::default(),
+    }
+}
+
+/// 读取 TASK_DAYS_MODE 环境变量，未配置或无法识别时按活跃天数计算
+fn task_days_mode(env: &Env) -> TaskDaysMode {
+    match env.var("TASK_DAYS_MODE") {
+        Ok(value) => {
+            let value = value.to_string();
+            TaskDaysMode::parse(&value).unwrap_or_else(|| {
+                console_log!("⚠️ 无法识别的 TASK_DAYS_MODE: {}，按活跃天数计算", value);
+                TaskDaysMode::default()
+            })
+        }
+        Err(_) => TaskDaysMode::default(),
+    }
+}
+
+/// 读取 TASK_RETENTION_DAYS 环境变量，未配置或无效时为 30 天，0 表示不归档
+fn task_retention_days(env: &Env) -> i64 {
+    match env.var("TASK_RETENTION_DAYS") {
+        Ok(value) => {
+            let value = value.to_string();
+            value.trim().parse::<i64>().ok().filter(|days| *days >= 0).unwrap_or_else(|| {
+                console_log!("⚠️ 无效的 TASK_RETENTION_DAYS: {}，使用 30 天", value);
+                DEFAULT_TASK_RETENTION_DAYS
+            })
+        }
+        Err(_) => DEFAULT_TASK_RETENTION_DAYS,
+    }
+}
+
+/// 根据 REDACTION_RULES 环境变量创建脱敏器，未配置时只启用内置的密钥识别
+fn create_redactor(env: &Env, pr_response: &github_api::GitHubSearchResponse, entries: &[github_api::StandupEntry]) -> anyhow::Result<Redactor> {
+    let rules = match env.var("REDACTION_RULES") {
+        Ok(rules) => RedactionRules::from_json(&rules.to_string())?,
+        Err(_) => RedactionRules::default(),
+    };
+
+    Redactor::new(&rules, pr_response, entries)
+}
+
+/// 记录 AI 调用用量，失败时只记录日志，不影响报告发送
+/// report_date 为成员本地的报告日期，login 为已解析的 GitHub 用户名
+async fn record_ai_usage(env: &Env, db_client: &DatabaseClient<'_>, login: &str, report_date: chrono::NaiveDate, completion: &ai_client::AICompletion) {
+    let currency = env.var("AI_PRICE_CURRENCY").map(|s| s.to_string()).unwrap_or_else(|_| "CNY".to_string());
+    let price_table = match env.var("AI_PRICE_TABLE") {
+        Ok(table) => PriceTable::from_json(&table.to_string(), currency.clone()).unwrap_or_else(|e| {
+            console_log!("⚠️ {}，成本按 0 计算", e);
+            PriceTable::empty(currency)
+        }),
+        Err(_) => PriceTable::empty(currency),
+    };
+
+    let record = AIUsageRecord {
+        user_login: login.to_string(),
+        run_date: report_date.format("%Y-%m-%d").to_string(),
+        model: completion.model.clone(),
+        prompt_tokens: completion.usage.prompt_tokens as i32,
+        completion_tokens: completion.usage.completion_tokens as i32,
+        total_tokens: completion.usage.total_tokens as i32,
+        latency_ms: completion.latency_ms as i32,
+        estimated_cost: price_table.estimate_cost(&completion.model, &completion.usage),
+        currency: price_table.currency().to_string(),
+    };
+
+    if let Err(e) = db_client.record_ai_usage(&record).await {
+        console_log!("⚠️ 记录 AI 用量失败: {}", e);
+    }
+}
+
+/// 成员的 GitHub 用户名，GITHUB_TOKEN 对应的用户需要通过 API 查询
+async fn member_login(github_client: &GitHubApiClient, member: &TeamMember) -> anyhow::Result<String> {
+    if member.is_token_owner() {
+        Ok(github_client.get_authenticated_user().await?.login)
+    } else {
+        Ok(member.login.clone())
+    }
+}
+
+/// 不持有 GitHub 客户端时解析成员的 GitHub 用户名
+async fn resolve_login(env: &Env, member: &TeamMember) -> anyhow::Result<String> {
+    if member.is_token_owner() {
+        let github_client = GitHubApiClient::new(env.var("GITHUB_TOKEN")?.to_string(), report_locale(env));
+        member_login(&github_client, member).await
+    } else {
+        Ok(member.login.clone())
+    }
+}
+
+/// 检查成员今日的运行是否失败或中断，且未超过最大尝试次数
+async fn needs_retry(env: &Env, member: &TeamMember, date: chrono::NaiveDate, now: chrono::DateTime<chrono::Utc>) -> anyhow::Result<bool> {
+    let login = resolve_login(env, member).await?;
+
+    let db = env.d1("DB")?;
+    let db_client = DatabaseClient::new(&db);
+    db_client.migrate().await?;
+    let run = db_client.get_standup_run(&login, &date.format("%Y-%m-%d").to_string()).await?;
+    Ok(run.map(|run| run.needs_retry(now)).unwrap_or(false))
+}
+
+/// 检查成员在指定日期是否请假
+async fn is_on_leave(env: &Env, member: &TeamMember, date: chrono::NaiveDate) -> anyhow::Result<bool> {
+    let login = resolve_login(env, member).await?;
+
+    let db = env.d1("DB")?;
+    let db_client = DatabaseClient::new(&db);
+    db_client.migrate().await?;
+    db_client.is_on_leave(&login, &date.format("%Y-%m-%d").to_string()).await
+}
+
+/// 按成员配置的日历检查指定日期是否为工作日
+/// 中国大陆日历优先查询 D1 中的本地节假日日历，本地缺少当年数据时才调用节假日 API 补全；
+/// 日历查询失败时按周一到周五上班处理
+async fn is_working_day(env: &Env, member: &TeamMember, date: chrono::NaiveDate) -> Result<bool> {
+    console_log!("正在查询日期 {} 的工作日状态...", date);
+    
+    let db = env.d1("DB")?;
+    let db_client = DatabaseClient::new(&db);
+    db_client.migrate().await
+        .map_err(|e| Error::RustError(format!("执行数据库迁移失败: {}", e)))?;
+
+    let api_url = env.var("HOLIDAY_API_URL").map(|s| s.to_string()).ok();
+    let status = match member_day_status(&db_client, member, date, api_url).await {
+        Ok(status) => status,
+        Err(e) => {
+            console_log!("⚠️ 查询 {} 的工作日日历失败: {}，按周一到周五上班处理", member.name(), e);
+            DayStatus::default_for(date)
         }
+    };
+
+    if status.is_working_day() {
+        console_log!("✓ 今日为{}", status.description());
+    } else {
+        console_log!("ℹ️ 今日为{}", status.description());
+    }
+
+    Ok(status.is_working_day())
+}
+
+/// 使用成员配置的日历查询日期状态
+async fn member_day_status(db_client: &DatabaseClient<'_>, member: &TeamMember, date: chrono::NaiveDate, api_url: Option<String>) -> anyhow::Result<DayStatus> {
+    let kind = member.calendar_kind()?;
+    let calendar = create_calendar(&kind, db_client, api_url).await?;
+    calendar.day_status(date).await
+}
+
+/// 创建成员的工作日日历，用于累积任务天数；日历不可用时按周一到周五上班处理
+async fn member_calendar<'a>(env: &Env, db_client: &'a DatabaseClient<'a>, member: &TeamMember) -> Box<dyn WorkingCalendar + 'a> {
+    let api_url = env.var("HOLIDAY_API_URL").map(|s| s.to_string()).ok();
+    let calendar = match member.calendar_kind() {
+        Ok(kind) => create_calendar(&kind, db_client, api_url).await,
+        Err(e) => Err(e),
+    };
+
+    calendar.unwrap_or_else(|e| {
+        console_log!("⚠️ 创建 {} 的工作日日历失败: {}，按周一到周五上班处理", member.name(), e);
+        Box::new(WeekendCalendar)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sse_event_encodes_data_as_json_string() {
+        assert_eq!(sse_event("token", "第一行\n\"第二行\""), "event: token\ndata: \"第一行\\n\\\"第二行\\\"\"\n\n");
+        assert_eq!(sse_event("done", ""), "event: done\ndata: \"\"\n\n");
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn sse_event_skips_empty_tokens() {
+        assert_eq!(sse_event("token", ""), "");
+    }
+}
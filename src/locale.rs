@@ -0,0 +1,328 @@
+use serde::{Deserialize, Serialize};
+
+/// 报告语言，通过 REPORT_LOCALE 环境变量配置（zh-CN / en），默认中文
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    Zh,
+    En,
+}
+
+impl Locale {
+    /// 解析语言标识，支持 zh、zh-CN、en、en-US 等写法
+    pub fn parse(value: &str) -> Option<Self> {
+        let lang = value.trim().to_lowercase();
+        let lang = lang.split(['-', '_']).next().unwrap_or("");
+        match lang {
+            "zh" => Some(Locale::Zh),
+            "en" => Some(Locale::En),
+            _ => None,
+        }
+    }
+
+    /// 当前语言的文案
+    pub fn texts(&self) -> &'static Texts {
+        match self {
+            Locale::Zh => &ZH,
+            Locale::En => &EN,
+        }
+    }
+}
+
+/// 报告中使用的全部文案
+pub struct Texts {
+    // 原始数据标签
+    pub data_header: &'static str,
+    pub summary_header: &'static str,
+    pub total_prs: &'static str,
+    pub completeness: &'static str,
+    pub partial_data: &'static str,
+    pub complete_data: &'static str,
+    pub no_prs: &'static str,
+    pub details_header: &'static str,
+    pub title: &'static str,
+    pub repo: &'static str,
+    pub status: &'static str,
+    pub merged: &'static str,
+    pub closed: &'static str,
+    pub open: &'static str,
+    pub taiga: &'static str,
+    pub days_prefix: &'static str,
+    pub days_suffix: &'static str,
+    pub task_done: &'static str,
+    pub project_code: &'static str,
+    pub work_summary: &'static str,
+    pub link: &'static str,
+    pub ai_guide_header: &'static str,
+
+    // AI 提示词
+    pub system_prompt: &'static str,
+    pub ai_prompt: &'static str,
+    pub placeholder_notice: &'static str,
+    pub feedback_header: &'static str,
+    pub feedback_footer: &'static str,
+    pub discrepancy_line_prefix: &'static str,
+    pub discrepancy_line_suffix: &'static str,
+    pub discrepancy_text_suffix: &'static str,
+    pub discrepancy_task: &'static str,
+    pub discrepancy_days_found: &'static str,
+    pub discrepancy_days_expected: &'static str,
+    pub discrepancy_unknown_task: &'static str,
+    pub discrepancy_pr: &'static str,
+    pub discrepancy_days: &'static str,
+    pub discrepancy_unverified_days: &'static str,
+
+    // 周报和月报
+    pub weekly_data_header: &'static str,
+    pub monthly_data_header: &'static str,
+    pub period_range: &'static str,
+    pub daily_reports_header: &'static str,
+    pub merged_prs_header: &'static str,
+    pub tasks_header: &'static str,
+    pub no_reports: &'static str,
+    pub no_merged_prs: &'static str,
+    pub summary_system_prompt: &'static str,
+    pub weekly_prompt: &'static str,
+    pub monthly_prompt: &'static str,
+
+    // 团队日报
+    pub digest_title: &'static str,
+    pub merged_count: &'static str,
+    pub open_count: &'static str,
+    pub digest_failed: &'static str,
+
+    // 飞书消息
+    pub feishu_title: &'static str,
+    pub weekly_title: &'static str,
+    pub monthly_title: &'static str,
+    pub generated_at: &'static str,
+}
+
+static ZH: Texts = Texts {
+    data_header: "=== 每日站会报告数据 ===",
+    summary_header: "## 原始 GitHub PR 数据摘要",
+    total_prs: "- 今日创建的 PR 总数：",
+    completeness: "- 数据完整性：",
+    partial_data: "部分数据",
+    complete_data: "完整数据",
+    no_prs: "今天没有创建任何 PR，可能没有代码提交工作完成。",
+    details_header: "## PR 详细信息",
+    title: "- 标题：",
+    repo: "- 仓库：",
+    status: "- 状态：",
+    merged: "已合并",
+    closed: "已关闭",
+    open: "进行中",
+    taiga: "- 关联 Taiga：",
+    days_prefix: " (累积",
+    days_suffix: "天)",
+    task_done: " - 完成",
+    project_code: "- 项目代号：",
+    work_summary: "- 工作内容：",
+    link: "- 链接：",
+    ai_guide_header: "## AI 处理指引",
+
+    system_prompt: "你是一个专业的项目管理助手，专门帮助生成每日站会报告。请严格按照用户提供的格式要求，基于 GitHub PR 数据生成简洁、专业的站会内容，并且不要输出多余的内容。",
+    ai_prompt: r#"请基于上述 GitHub PR 数据，生成符合以下格式的每日站会报告：
+
+格式要求：
+- 有对应 Taiga issue 的：[天数]项目代号#Taiga编号-工作内容
+- 无对应 Taiga issue 的：[天数]工作内容
+- [天数] 是指这项工作到目前为止累积的天数（在原始数据中已经标注了累积天数）
+
+示例：
+[2]xxxAsk#3-重构登录逻辑
+[1]ktv#15-完成功能开发，准备测试
+[3]学习Flutter和Dart
+
+生成要求：
+
+1. 内容应简洁明了，避免冗长描述
+2. 机密项目应避免透露敏感信息
+3. 耗时一小时以下的工作无需汇报
+4. 根据 PR 状态推断工作进度：
+   - 已合并的 PR = 工作已完成
+   - 进行中的 PR = 工作正在进行
+   - 已关闭未合并的 PR = 工作可能取消或需要重新开始
+5. 项目代号直接使用原始数据中"项目代号"一栏的内容，不要修改大小写或增删字符
+6. 优先使用原始数据中的累积天数信息，如果原始数据中标注了"(累积X天)"，则使用该天数
+7. 如果没有累积天数信息，根据 PR 状态推断是新工作还是持续工作
+8. 原始数据中标注了"完成"的任务，关联的 PR 已全部合并，请在工作内容末尾注明"完成"
+
+请基于上述数据生成今日的站会报告内容。
+请输出纯文本，不要使用 markdown，不要包含任何 markdown 语法。
+为了避免涉密，请不要输出任何与项目相关的信息，尽量简要描述今天的工作内容就够了。"#,
+    placeholder_notice: "\n注意：形如 Proj1 的占位符是脱敏后的名称，请在输出中原样保留，不要改写或翻译。",
+    feedback_header: "\n\n## 上一次生成的问题\n上一次生成的报告与原始数据不一致，请修正：\n",
+    feedback_footer: "只能使用原始数据中出现的 Taiga 编号和累积天数，不要编造。\n",
+    discrepancy_line_prefix: "第 ",
+    discrepancy_line_suffix: " 行「",
+    discrepancy_text_suffix: "」：",
+    discrepancy_task: "任务 #",
+    discrepancy_days_found: " 的天数为 ",
+    discrepancy_days_expected: "，原始数据为 ",
+    discrepancy_unknown_task: " 不在原始数据中",
+    discrepancy_pr: "PR ",
+    discrepancy_days: "天数 ",
+    discrepancy_unverified_days: " 无法从原始数据中找到依据",
+
+    weekly_data_header: "=== 周报数据 ===",
+    monthly_data_header: "=== 月报数据 ===",
+    period_range: "- 时间范围：",
+    daily_reports_header: "## 每日站会报告",
+    merged_prs_header: "## 已合并的 PR",
+    tasks_header: "## Taiga 任务累积天数",
+    no_reports: "该时间段内没有站会报告。",
+    no_merged_prs: "该时间段内没有合并的 PR。",
+    summary_system_prompt: "你是一个专业的项目管理助手，专门帮助把每日站会报告整理成周报和月报。请严格按照用户提供的格式要求，基于每日报告、已合并的 PR 和任务天数总结这段时间的工作，并且不要输出多余的内容。",
+    weekly_prompt: r#"请基于上述数据，生成本周的周报：
+
+格式要求：
+1. 本周完成：按项目归纳已完成的工作，每项一行
+2. 进行中：仍在进行的工作及累积天数，格式为 [天数]项目代号#Taiga编号-工作内容
+3. 下周计划：根据进行中的工作推断下周的重点
+
+生成要求：
+- 合并同一任务在不同日期的多条记录，不要逐日罗列
+- 只能使用原始数据中出现的 Taiga 编号和累积天数，不要编造
+- 内容简洁，总共不超过 15 行
+- 请输出纯文本，不要使用 markdown 语法"#,
+    monthly_prompt: r#"请基于上述数据，生成本月的月报：
+
+格式要求：
+1. 本月概览：一两句话总结本月的工作重点
+2. 主要成果：按项目归纳已完成的工作，每项一行
+3. 进行中：仍在进行的工作及累积天数，格式为 [天数]项目代号#Taiga编号-工作内容
+4. 下月计划：根据进行中的工作推断下月的重点
+
+生成要求：
+- 合并同一任务在不同日期的多条记录，不要逐日罗列
+- 只能使用原始数据中出现的 Taiga 编号和累积天数，不要编造
+- 内容简洁，总共不超过 20 行
+- 请输出纯文本，不要使用 markdown 语法"#,
+
+    digest_title: "👥 团队站会日报",
+    merged_count: "已合并 PR：",
+    open_count: "进行中 PR：",
+    digest_failed: "报告生成失败：",
+
+    feishu_title: "📋 每日站会报告",
+    weekly_title: "📅 周报",
+    monthly_title: "🗓️ 月报",
+    generated_at: "⏰ 生成时间: ",
+};
+
+static EN: Texts = Texts {
+    data_header: "=== Daily Stand-up Report Data ===",
+    summary_header: "## Raw GitHub PR Summary",
+    total_prs: "- PRs created today: ",
+    completeness: "- Data completeness: ",
+    partial_data: "partial",
+    complete_data: "complete",
+    no_prs: "No PRs were created today; there may be no completed code work.",
+    details_header: "## PR Details",
+    title: "- Title: ",
+    repo: "- Repository: ",
+    status: "- Status: ",
+    merged: "merged",
+    closed: "closed",
+    open: "in progress",
+    taiga: "- Taiga: ",
+    days_prefix: " (",
+    days_suffix: " days so far)",
+    task_done: " - done",
+    project_code: "- Project code: ",
+    work_summary: "- Work: ",
+    link: "- Link: ",
+    ai_guide_header: "## Instructions for the AI",
+
+    system_prompt: "You are a professional project management assistant that writes daily stand-up reports. Strictly follow the format the user gives, write concise and professional stand-up content based on the GitHub PR data, and do not output anything extra.",
+    ai_prompt: r#"Based on the GitHub PR data above, write a daily stand-up report in the following format:
+
+Format:
+- Work with a Taiga issue: [days]ProjectCode#TaigaId-description
+- Work without a Taiga issue: [days]description
+- [days] is the number of days this work has accumulated so far (already given in the raw data)
+
+Examples:
+[2]xxxAsk#3-Refactor login flow
+[1]ktv#15-Finish feature, ready for testing
+[3]Learn Flutter and Dart
+
+Requirements:
+
+1. Keep it short and clear, avoid long descriptions
+2. Do not reveal sensitive information about confidential projects
+3. Work that took less than an hour does not need to be reported
+4. Infer progress from the PR status:
+   - merged PR = work done
+   - in-progress PR = work ongoing
+   - closed without merge = work possibly cancelled or restarting
+5. Use the "Project code" from the raw data exactly as given; do not change its case or add or remove characters
+6. Prefer the accumulated day counts from the raw data; if the data says "(X days so far)", use that number
+7. If there is no day count, infer from the PR status whether it is new or ongoing work
+8. Tasks marked "done" in the raw data have all their PRs merged; end their description with "done"
+
+Write today's stand-up report based on the data above.
+Output plain text only, no markdown syntax at all.
+To avoid leaking confidential details, do not output project-specific information; a brief description of today's work is enough."#,
+    placeholder_notice: "\nNote: placeholders such as Proj1 are redacted names. Keep them exactly as they are in the output; do not rewrite or translate them.",
+    feedback_header: "\n\n## Problems with the previous attempt\nThe previous report did not match the raw data. Please fix the following:\n",
+    feedback_footer: "Only use Taiga ids and day counts that appear in the raw data; do not make them up.\n",
+    discrepancy_line_prefix: "Line ",
+    discrepancy_line_suffix: " \"",
+    discrepancy_text_suffix: "\": ",
+    discrepancy_task: "task #",
+    discrepancy_days_found: " has day count ",
+    discrepancy_days_expected: " but the raw data says ",
+    discrepancy_unknown_task: " is not in the raw data",
+    discrepancy_pr: "PR ",
+    discrepancy_days: "day count ",
+    discrepancy_unverified_days: " cannot be found in the raw data",
+
+    weekly_data_header: "=== Weekly Report Data ===",
+    monthly_data_header: "=== Monthly Report Data ===",
+    period_range: "- Period: ",
+    daily_reports_header: "## Daily Stand-up Reports",
+    merged_prs_header: "## Merged PRs",
+    tasks_header: "## Taiga Task Day Counts",
+    no_reports: "There are no stand-up reports in this period.",
+    no_merged_prs: "No PRs were merged in this period.",
+    summary_system_prompt: "You are a professional project management assistant that turns daily stand-up reports into weekly and monthly reports. Strictly follow the format the user gives, summarize the period based on the daily reports, merged PRs and task day counts, and do not output anything extra.",
+    weekly_prompt: r#"Based on the data above, write this week's report:
+
+Format:
+1. Done this week: completed work grouped by project, one item per line
+2. In progress: ongoing work with its day count, formatted as [days]ProjectCode#TaigaId-description
+3. Next week: the focus for next week inferred from the ongoing work
+
+Requirements:
+- Merge entries for the same task across days; do not list day by day
+- Only use Taiga ids and day counts that appear in the raw data; do not make them up
+- Keep it short, no more than 15 lines in total
+- Output plain text only, no markdown syntax"#,
+    monthly_prompt: r#"Based on the data above, write this month's report:
+
+Format:
+1. Overview: one or two sentences on this month's focus
+2. Key results: completed work grouped by project, one item per line
+3. In progress: ongoing work with its day count, formatted as [days]ProjectCode#TaigaId-description
+4. Next month: the focus for next month inferred from the ongoing work
+
+Requirements:
+- Merge entries for the same task across days; do not list day by day
+- Only use Taiga ids and day counts that appear in the raw data; do not make them up
+- Keep it short, no more than 20 lines in total
+- Output plain text only, no markdown syntax"#,
+
+    digest_title: "👥 Team Stand-up Digest",
+    merged_count: "Merged PRs: ",
+    open_count: "Open PRs: ",
+    digest_failed: "Report failed: ",
+
+    feishu_title: "📋 Daily Stand-up Report",
+    weekly_title: "📅 Weekly Report",
+    monthly_title: "🗓️ Monthly Report",
+    generated_at: "⏰ Generated at: ",
+};
@@ -0,0 +1,103 @@
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use chrono::NaiveDate;
+use anyhow::Result;
+use futures::future::LocalBoxFuture;
+
+use crate::database::TaigaTaskRecord;
+use crate::leave::{leave_days, LeaveRecord};
+use crate::task_store::TaskStore;
+
+/// 保存在内存中的任务存储，用于本地测试天数计算
+#[derive(Default)]
+pub struct MemoryTaskStore {
+    tasks: RefCell<HashMap<String, TaigaTaskRecord>>,
+    archived: RefCell<HashMap<String, TaigaTaskRecord>>,
+    activity: RefCell<BTreeSet<(String, String)>>,
+    leave: RefCell<Vec<LeaveRecord>>,
+}
+
+impl MemoryTaskStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加请假记录
+    pub fn add_leave(&self, leave: &LeaveRecord) -> Result<()> {
+        leave.date_range()?;
+        self.leave.borrow_mut().push(leave.clone());
+        Ok(())
+    }
+}
+
+impl TaskStore for MemoryTaskStore {
+    fn get_task<'b>(&'b self, task_key: &'b str) -> LocalBoxFuture<'b, Result<Option<TaigaTaskRecord>>> {
+        Box::pin(async move { Ok(self.tasks.borrow().get(task_key).cloned()) })
+    }
+
+    fn save_task<'b>(&'b self, record: &'b TaigaTaskRecord) -> LocalBoxFuture<'b, Result<()>> {
+        Box::pin(async move {
+            self.tasks.borrow_mut().insert(record.task_key.clone(), record.clone());
+            Ok(())
+        })
+    }
+
+    fn record_activity<'b>(&'b self, task_key: &'b str, dates: &'b [&'b str]) -> LocalBoxFuture<'b, Result<()>> {
+        Box::pin(async move {
+            let mut activity = self.activity.borrow_mut();
+            for date in dates {
+                activity.insert((task_key.to_string(), date.to_string()));
+            }
+            Ok(())
+        })
+    }
+
+    fn count_active_days<'b>(&'b self, task_key: &'b str) -> LocalBoxFuture<'b, Result<i32>> {
+        Box::pin(async move {
+            Ok(self.activity.borrow().iter().filter(|(key, _)| key == task_key).count() as i32)
+        })
+    }
+
+    fn leave_days<'b>(&'b self, login: &'b str, start_date: &'b str, end_date: &'b str) -> LocalBoxFuture<'b, Result<HashSet<NaiveDate>>> {
+        Box::pin(async move {
+            let leaves: Vec<LeaveRecord> = self.leave.borrow().iter()
+                .filter(|leave| {
+                    leave.login == login
+                        && leave.start_date.as_str() <= end_date
+                        && leave.end_date.as_str() >= start_date
+                })
+                .cloned()
+                .collect();
+            Ok(leave_days(&leaves))
+        })
+    }
+
+    fn archive_tasks<'b>(&'b self, cutoff: &'b str) -> LocalBoxFuture<'b, Result<usize>> {
+        Box::pin(async move {
+            let mut tasks = self.tasks.borrow_mut();
+            let stale: Vec<String> = tasks.values()
+                .filter(|record| record.last_seen_date.as_str() < cutoff)
+                .map(|record| record.task_key.clone())
+                .collect();
+
+            let mut archived = self.archived.borrow_mut();
+            for task_key in &stale {
+                if let Some(record) = tasks.remove(task_key) {
+                    archived.insert(task_key.clone(), record);
+                }
+            }
+            Ok(stale.len())
+        })
+    }
+
+    fn restore_task<'b>(&'b self, task_key: &'b str) -> LocalBoxFuture<'b, Result<Option<TaigaTaskRecord>>> {
+        Box::pin(async move {
+            let record = match self.archived.borrow_mut().remove(task_key) {
+                Some(record) => record,
+                None => return Ok(None),
+            };
+            let mut tasks = self.tasks.borrow_mut();
+            Ok(Some(tasks.entry(task_key.to_string()).or_insert(record).clone()))
+        })
+    }
+}
@@ -0,0 +1,346 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// 迁移中的一个步骤
+pub enum MigrationStep {
+    /// 直接执行的 SQL
+    Sql(&'static str),
+    /// 表中缺少该列时才添加，兼容引入迁移之前已手动加过列的数据库
+    AddColumn {
+        table: &'static str,
+        column: &'static str,
+        definition: &'static str,
+    },
+}
+
+/// 一个编号的数据库迁移，按版本号顺序执行，每个版本只执行一次
+pub struct Migration {
+    pub version: i32,
+    pub name: &'static str,
+    pub steps: &'static [MigrationStep],
+}
+
+/// 所有迁移，新增表或列时在末尾追加新版本，不要修改已发布的迁移
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "taiga_tasks",
+        steps: &[
+            MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS taiga_tasks (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    task_key TEXT UNIQUE NOT NULL,
+                    first_seen_date TEXT NOT NULL,
+                    last_seen_date TEXT NOT NULL,
+                    total_days INTEGER NOT NULL DEFAULT 1,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                )
+            "#),
+            MigrationStep::Sql("CREATE INDEX IF NOT EXISTS idx_taiga_tasks_key ON taiga_tasks(task_key)"),
+            MigrationStep::Sql("CREATE INDEX IF NOT EXISTS idx_taiga_tasks_last_seen ON taiga_tasks(last_seen_date)"),
+        ],
+    },
+    Migration {
+        version: 2,
+        name: "ai_usage",
+        steps: &[
+            MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS ai_usage (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    user_login TEXT NOT NULL,
+                    run_date TEXT NOT NULL,
+                    model TEXT NOT NULL,
+                    prompt_tokens INTEGER NOT NULL DEFAULT 0,
+                    completion_tokens INTEGER NOT NULL DEFAULT 0,
+                    total_tokens INTEGER NOT NULL DEFAULT 0,
+                    latency_ms INTEGER NOT NULL DEFAULT 0,
+                    estimated_cost REAL NOT NULL DEFAULT 0,
+                    currency TEXT NOT NULL DEFAULT 'CNY',
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                )
+            "#),
+            MigrationStep::Sql("CREATE INDEX IF NOT EXISTS idx_ai_usage_run_date ON ai_usage(run_date)"),
+        ],
+    },
+    Migration {
+        version: 3,
+        name: "holiday_calendar",
+        steps: &[
+            MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS holiday_calendar (
+                    date TEXT PRIMARY KEY,
+                    year INTEGER NOT NULL,
+                    status INTEGER NOT NULL,
+                    name TEXT,
+                    source TEXT NOT NULL DEFAULT 'import'
+                )
+            "#),
+            MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS holiday_calendar_years (
+                    year INTEGER PRIMARY KEY,
+                    source TEXT NOT NULL,
+                    imported_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                )
+            "#),
+        ],
+    },
+    Migration {
+        version: 4,
+        name: "team_members",
+        steps: &[
+            MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS team_members (
+                    login TEXT PRIMARY KEY,
+                    display_name TEXT,
+                    calendar TEXT NOT NULL DEFAULT 'cn',
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                )
+            "#),
+            MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS ics_calendars (
+                    name TEXT PRIMARY KEY,
+                    content TEXT NOT NULL,
+                    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                )
+            "#),
+        ],
+    },
+    Migration {
+        version: 5,
+        name: "task_activity",
+        steps: &[
+            MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS task_activity (
+                    task_key TEXT NOT NULL,
+                    activity_date TEXT NOT NULL,
+                    PRIMARY KEY (task_key, activity_date)
+                )
+            "#),
+        ],
+    },
+    Migration {
+        version: 6,
+        name: "leave",
+        steps: &[
+            MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS leave (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    login TEXT NOT NULL,
+                    start_date TEXT NOT NULL,
+                    end_date TEXT NOT NULL,
+                    reason TEXT,
+                    source TEXT NOT NULL DEFAULT 'manual',
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                )
+            "#),
+            MigrationStep::Sql("CREATE INDEX IF NOT EXISTS idx_leave_login_dates ON leave (login, start_date, end_date)"),
+        ],
+    },
+    Migration {
+        version: 7,
+        name: "team_member_timezone",
+        steps: &[
+            MigrationStep::AddColumn {
+                table: "team_members",
+                column: "timezone",
+                definition: "TEXT NOT NULL DEFAULT 'Asia/Shanghai'",
+            },
+            MigrationStep::AddColumn {
+                table: "team_members",
+                column: "send_time",
+                definition: "TEXT NOT NULL DEFAULT '18:00'",
+            },
+        ],
+    },
+    Migration {
+        version: 8,
+        name: "standup_runs",
+        steps: &[
+            MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS standup_runs (
+                    login TEXT NOT NULL,
+                    run_date TEXT NOT NULL,
+                    status TEXT NOT NULL,
+                    report TEXT,
+                    channels TEXT,
+                    error TEXT,
+                    attempts INTEGER NOT NULL DEFAULT 0,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    PRIMARY KEY (login, run_date)
+                )
+            "#),
+        ],
+    },
+    Migration {
+        version: 9,
+        name: "standup_reports",
+        steps: &[
+            MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS standup_reports (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    login TEXT NOT NULL,
+                    report_date TEXT NOT NULL,
+                    report TEXT NOT NULL,
+                    raw_data TEXT NOT NULL,
+                    entries TEXT NOT NULL DEFAULT '[]',
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                )
+            "#),
+            MigrationStep::Sql("CREATE INDEX IF NOT EXISTS idx_standup_reports_login_date ON standup_reports (login, report_date)"),
+        ],
+    },
+    Migration {
+        version: 10,
+        name: "archived_tasks",
+        steps: &[
+            MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS archived_tasks (
+                    task_key TEXT PRIMARY KEY,
+                    first_seen_date TEXT NOT NULL,
+                    last_seen_date TEXT NOT NULL,
+                    total_days INTEGER NOT NULL DEFAULT 1,
+                    archived_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                )
+            "#),
+        ],
+    },
+    Migration {
+        version: 11,
+        name: "task_status",
+        steps: &[
+            MigrationStep::AddColumn {
+                table: "taiga_tasks",
+                column: "status",
+                definition: "TEXT NOT NULL DEFAULT 'open'",
+            },
+            MigrationStep::AddColumn {
+                table: "taiga_tasks",
+                column: "extra_days",
+                definition: "INTEGER NOT NULL DEFAULT 0",
+            },
+            MigrationStep::AddColumn {
+                table: "archived_tasks",
+                column: "status",
+                definition: "TEXT NOT NULL DEFAULT 'open'",
+            },
+            MigrationStep::AddColumn {
+                table: "archived_tasks",
+                column: "extra_days",
+                definition: "INTEGER NOT NULL DEFAULT 0",
+            },
+        ],
+    },
+    Migration {
+        version: 12,
+        name: "pr_task_links",
+        steps: &[
+            MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS pr_task_links (
+                    repo TEXT NOT NULL,
+                    pr_number INTEGER NOT NULL,
+                    task_key TEXT NOT NULL,
+                    first_linked_date TEXT NOT NULL,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    PRIMARY KEY (repo, pr_number, task_key)
+                )
+            "#),
+            MigrationStep::Sql("CREATE INDEX IF NOT EXISTS idx_pr_task_links_task ON pr_task_links(task_key)"),
+        ],
+    },
+    Migration {
+        version: 13,
+        name: "project_code_rules",
+        steps: &[
+            MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS project_code_rules (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    kind TEXT NOT NULL,
+                    pattern TEXT NOT NULL,
+                    replacement TEXT NOT NULL DEFAULT '',
+                    position INTEGER NOT NULL DEFAULT 0,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    UNIQUE (kind, pattern)
+                )
+            "#),
+        ],
+    },
+    Migration {
+        version: 14,
+        name: "summary_runs",
+        steps: &[
+            MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS summary_runs (
+                    login TEXT NOT NULL,
+                    period TEXT NOT NULL,
+                    period_end TEXT NOT NULL,
+                    status TEXT NOT NULL,
+                    error TEXT,
+                    attempts INTEGER NOT NULL DEFAULT 0,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    PRIMARY KEY (login, period, period_end)
+                )
+            "#),
+        ],
+    },
+    Migration {
+        version: 15,
+        name: "digest_runs",
+        steps: &[
+            MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS digest_runs (
+                    digest_date TEXT PRIMARY KEY,
+                    status TEXT NOT NULL,
+                    report TEXT,
+                    error TEXT,
+                    attempts INTEGER NOT NULL DEFAULT 0,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                )
+            "#),
+        ],
+    },
+    Migration {
+        version: 16,
+        name: "pr_task_links_merged_at",
+        steps: &[
+            MigrationStep::AddColumn {
+                table: "pr_task_links",
+                column: "merged_at",
+                definition: "TEXT",
+            },
+        ],
+    },
+    Migration {
+        version: 17,
+        name: "task_status_source",
+        steps: &[
+            MigrationStep::AddColumn {
+                table: "taiga_tasks",
+                column: "status_source",
+                definition: "TEXT NOT NULL DEFAULT 'auto'",
+            },
+            MigrationStep::AddColumn {
+                table: "archived_tasks",
+                column: "status_source",
+                definition: "TEXT NOT NULL DEFAULT 'auto'",
+            },
+        ],
+    },
+];
+
+/// 当前 Worker 实例是否已检查并执行过迁移
+static MIGRATIONS_CHECKED: AtomicBool = AtomicBool::new(false);
+
+/// 当前实例是否已完成迁移检查
+pub fn migrations_checked() -> bool {
+    MIGRATIONS_CHECKED.load(Ordering::Relaxed)
+}
+
+/// 标记当前实例已完成迁移检查，之后的请求不再查询 schema_migrations
+pub fn mark_migrations_checked() {
+    MIGRATIONS_CHECKED.store(true, Ordering::Relaxed);
+}
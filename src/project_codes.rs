@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use anyhow::{Result, anyhow};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// 项目代号规则的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleKind {
+    /// 仓库（owner/repo）精确对应一个代号
+    Repo,
+    /// 对仓库全名做正则替换，用于去掉组织名、_flutter 等前后缀
+    Regex,
+}
+
+impl RuleKind {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "repo" => Some(RuleKind::Repo),
+            "regex" => Some(RuleKind::Regex),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RuleKind::Repo => "repo",
+            RuleKind::Regex => "regex",
+        }
+    }
+}
+
+/// 仓库到项目代号的规则，保存在 project_code_rules 表中
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectCodeRule {
+    #[serde(default)]
+    pub id: Option<i64>,
+    pub kind: RuleKind,
+    /// repo 规则为 owner/repo，regex 规则为正则表达式
+    pub pattern: String,
+    /// repo 规则为项目代号，regex 规则为替换内容（可以为空，表示删除匹配部分）
+    #[serde(default)]
+    pub replacement: String,
+    /// regex 规则的执行顺序，从小到大
+    #[serde(default)]
+    pub position: i32,
+}
+
+impl ProjectCodeRule {
+    pub fn validate(&self) -> Result<()> {
+        if self.pattern.trim().is_empty() {
+            return Err(anyhow!("pattern 不能为空"));
+        }
+        match self.kind {
+            RuleKind::Repo => {
+                if !self.pattern.contains('/') {
+                    return Err(anyhow!("仓库名无效: {}，请使用 owner/repo 格式", self.pattern));
+                }
+                if self.replacement.trim().is_empty() {
+                    return Err(anyhow!("仓库 {} 的项目代号不能为空", self.pattern));
+                }
+            }
+            RuleKind::Regex => {
+                Regex::new(&self.pattern)
+                    .map_err(|e| anyhow!("正则表达式 {} 无效: {}", self.pattern, e))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 根据规则计算仓库的项目代号：先查精确映射，没有时依次执行正则替换，再取仓库名部分
+/// 不做大小写转换，代号保持规则或仓库名中的写法
+#[derive(Debug, Default)]
+pub struct ProjectCodes {
+    repos: HashMap<String, String>,
+    rules: Vec<(Regex, String)>,
+}
+
+impl ProjectCodes {
+    pub fn new(rules: &[ProjectCodeRule]) -> Result<Self> {
+        let mut repos = HashMap::new();
+        let mut regex_rules: Vec<&ProjectCodeRule> = Vec::new();
+        for rule in rules {
+            rule.validate()?;
+            match rule.kind {
+                RuleKind::Repo => {
+                    repos.insert(rule.pattern.trim().to_lowercase(), rule.replacement.trim().to_string());
+                }
+                RuleKind::Regex => regex_rules.push(rule),
+            }
+        }
+        regex_rules.sort_by_key(|rule| rule.position);
+
+        let rules = regex_rules.into_iter()
+            .map(|rule| {
+                Regex::new(&rule.pattern)
+                    .map(|regex| (regex, rule.replacement.clone()))
+                    .map_err(|e| anyhow!("正则表达式 {} 无效: {}", rule.pattern, e))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { repos, rules })
+    }
+
+    /// 仓库（owner/repo）对应的项目代号
+    pub fn code_for(&self, repo: &str) -> String {
+        if let Some(code) = self.repos.get(&repo.to_lowercase()) {
+            return code.clone();
+        }
+
+        let mut name = repo.to_string();
+        for (regex, replacement) in &self.rules {
+            name = regex.replace_all(&name, replacement.as_str()).into_owned();
+        }
+
+        let code = name.rsplit('/').next().unwrap_or("").trim();
+        if code.is_empty() {
+            repo.rsplit('/').next().unwrap_or(repo).to_string()
+        } else {
+            code.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(kind: RuleKind, pattern: &str, replacement: &str, position: i32) -> ProjectCodeRule {
+        ProjectCodeRule {
+            id: None,
+            kind,
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+            position,
+        }
+    }
+
+    #[test]
+    fn defaults_to_repo_name() {
+        let codes = ProjectCodes::default();
+        assert_eq!(codes.code_for("acme/Billing"), "Billing");
+    }
+
+    #[test]
+    fn repo_rules_match_case_insensitively_before_regex_rules() {
+        let codes = ProjectCodes::new(&[
+            rule(RuleKind::Repo, "Acme/Billing-Service", "BILL", 0),
+            rule(RuleKind::Regex, "-service$", "", 0),
+        ]).unwrap();
+        assert_eq!(codes.code_for("acme/billing-service"), "BILL");
+        assert_eq!(codes.code_for("acme/search-service"), "search");
+    }
+
+    #[test]
+    fn regex_rules_run_in_position_order() {
+        let codes = ProjectCodes::new(&[
+            rule(RuleKind::Regex, "_app$", "", 2),
+            rule(RuleKind::Regex, "_flutter", "_app", 1),
+        ]).unwrap();
+        assert_eq!(codes.code_for("acme/shop_flutter"), "shop");
+    }
+
+    #[test]
+    fn falls_back_to_repo_name_when_rules_remove_everything() {
+        let codes = ProjectCodes::new(&[rule(RuleKind::Regex, ".*", "", 0)]).unwrap();
+        assert_eq!(codes.code_for("acme/web"), "web");
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        assert!(rule(RuleKind::Repo, "web", "WEB", 0).validate().is_err());
+        assert!(rule(RuleKind::Repo, "acme/web", " ", 0).validate().is_err());
+        assert!(rule(RuleKind::Regex, "(", "", 0).validate().is_err());
+        assert!(rule(RuleKind::Regex, " ", "", 0).validate().is_err());
+        assert!(ProjectCodes::new(&[rule(RuleKind::Regex, "[", "", 0)]).is_err());
+        assert_eq!(RuleKind::parse(" Regex "), Some(RuleKind::Regex));
+        assert_eq!(RuleKind::parse("glob"), None);
+    }
+}
@@ -0,0 +1,405 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use anyhow::{Result, anyhow};
+use regex::Regex;
+
+use crate::github_api::{GitHubSearchResponse, StandupEntry};
+
+/// 不可逆替换的占位文本（密钥、Token 等不会被还原）
+const SECRET_PLACEHOLDER: &str = "[REDACTED]";
+
+/// 内置的密钥/Token 识别规则
+const SECRET_PATTERNS: &[&str] = &[
+    r"gh[pousr]_[A-Za-z0-9]{36,}",                                // GitHub Token
+    r"github_pat_[A-Za-z0-9_]{22,}",                              // GitHub 细粒度 Token
+    r"sk-[A-Za-z0-9_\-]{20,}",                                    // OpenAI 兼容 API Key
+    r"AKIA[0-9A-Z]{16}",                                          // AWS Access Key
+    r"xox[abposr]-[A-Za-z0-9\-]{10,}",                            // Slack Token
+    r"eyJ[A-Za-z0-9_\-]+\.[A-Za-z0-9_\-]+\.[A-Za-z0-9_\-]+",      // JWT
+    r"https://open\.(?:feishu\.cn|larksuite\.com)/open-apis/bot/v2/hook/[A-Za-z0-9\-]+", // 飞书 Webhook
+    r"(?s)-----BEGIN [A-Z ]*PRIVATE KEY-----.*?-----END [A-Z ]*PRIVATE KEY-----",
+    r#"(?i)\b(?:password|passwd|pwd|secret|token|api[_\-]?key)\b\s*[:=]\s*[^\s"']+"#,
+];
+
+/// 自定义脱敏规则
+#[derive(Debug, Clone, Deserialize)]
+pub struct RedactionPattern {
+    pub pattern: String,
+    /// 占位符前缀，例如 "Client" 会生成 Client1、Client2
+    #[serde(default = "default_pattern_label")]
+    pub label: String,
+}
+
+fn default_pattern_label() -> String {
+    "Redacted".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// 脱敏配置，来自 REDACTION_RULES 环境变量（JSON）
+#[derive(Debug, Clone, Deserialize)]
+pub struct RedactionRules {
+    /// 允许原样发送给 AI 的仓库（owner/repo）或 Taiga 项目 slug；
+    /// 配置后，不在列表中的仓库名和 Taiga slug 都会被替换为占位符
+    #[serde(default)]
+    pub repo_allowlist: Option<Vec<String>>,
+    /// 自定义正则规则
+    #[serde(default)]
+    pub patterns: Vec<RedactionPattern>,
+    /// 项目代号 → 别名映射，发送前替换为别名，生成后还原
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
+    /// 是否启用内置的密钥/Token 识别
+    #[serde(default = "default_true")]
+    pub detect_secrets: bool,
+}
+
+impl Default for RedactionRules {
+    fn default() -> Self {
+        Self {
+            repo_allowlist: None,
+            patterns: Vec::new(),
+            aliases: HashMap::new(),
+            detect_secrets: true,
+        }
+    }
+}
+
+impl RedactionRules {
+    /// 从 JSON 字符串解析脱敏配置
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| anyhow!("解析脱敏配置失败: {}", e))
+    }
+}
+
+/// 代号别名，脱敏和还原使用相同的匹配规则
+struct Alias {
+    codename: String,
+    codename_regex: Regex,
+    alias: String,
+    alias_regex: Regex,
+}
+
+/// 占位符及其原始内容，正则在分配占位符时编译一次
+struct Placeholder {
+    placeholder: String,
+    original: String,
+    regex: Regex,
+}
+
+/// 脱敏器：在发送给 AI 之前替换敏感内容，并在生成后还原占位符
+pub struct Redactor {
+    secret_regexes: Vec<Regex>,
+    pattern_regexes: Vec<(Regex, String)>,
+    aliases: Vec<Alias>,
+    /// 需要替换的名称的正则及其还原值，按名称长度降序排列，避免短名称先匹配
+    names: Vec<(Regex, String)>,
+    /// 占位符 → 原始内容，按占位符长度降序排列，避免 Proj1 误匹配 Proj10
+    placeholders: Vec<Placeholder>,
+    /// (占位符前缀, 原始内容) → 占位符，相同内容复用同一个占位符
+    assigned: HashMap<(String, String), String>,
+    counters: HashMap<String, usize>,
+}
+
+impl Redactor {
+    /// 根据脱敏配置、当天的 PR 数据和整理后的站会数据（用于获取项目代号）创建脱敏器
+    pub fn new(rules: &RedactionRules, response: &GitHubSearchResponse, entries: &[StandupEntry]) -> Result<Self> {
+        let repos: Vec<String> = response.items.iter()
+            .map(|pr| pr.repository_url.replace("https://api.github.com/repos/", ""))
+            .collect();
+        let codes = project_codes(entries);
+        let texts: Vec<String> = response.items.iter()
+            .map(|pr| format!("{} {}", pr.title, pr.body.as_deref().unwrap_or("")))
+            .collect();
+
+        Self::from_sources(rules, &repos, &codes, &texts)
+    }
+
+    /// 根据脱敏配置、涉及的仓库（owner/repo）、仓库对应的项目代号和可能包含 Taiga 链接的文本创建脱敏器
+    pub fn from_sources(rules: &RedactionRules, repos: &[String], codes: &[(String, String)], texts: &[String]) -> Result<Self> {
+        let secret_regexes = if rules.detect_secrets {
+            SECRET_PATTERNS.iter()
+                .map(|p| Regex::new(p).map_err(|e| anyhow!("内置脱敏规则无效: {}", e)))
+                .collect::<Result<Vec<_>>>()?
+        } else {
+            Vec::new()
+        };
+
+        let pattern_regexes = rules.patterns.iter()
+            .map(|p| {
+                Regex::new(&p.pattern)
+                    .map(|regex| (regex, p.label.clone()))
+                    .map_err(|e| anyhow!("脱敏规则 {} 无效: {}", p.pattern, e))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let aliases = rules.aliases.iter()
+            .filter(|(codename, alias)| !codename.is_empty() && !alias.is_empty())
+            .map(|(codename, alias)| {
+                Ok(Alias {
+                    codename: codename.clone(),
+                    codename_regex: literal_regex(codename)?,
+                    alias: alias.clone(),
+                    alias_regex: literal_regex(alias)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut names = Vec::new();
+        if let Some(allowlist) = &rules.repo_allowlist {
+            let is_allowed = |name: &str| allowlist.iter().any(|allowed| allowed.eq_ignore_ascii_case(name));
+
+            for full_name in repos {
+                if is_allowed(full_name) {
+                    continue;
+                }
+                let short_name = full_name.split('/').next_back().unwrap_or(full_name).to_string();
+                names.push((full_name.clone(), short_name.clone()));
+                names.push((short_name.clone(), short_name));
+            }
+
+            // 项目代号可能与仓库名不同，按所属仓库是否允许一并脱敏
+            for (repo, code) in codes {
+                if !is_allowed(repo) {
+                    names.push((code.clone(), code.clone()));
+                }
+            }
+
+            let taiga_slug_regex = Regex::new(r"\.taiga\.io/project/([^/\s]+)/").unwrap();
+            for text in texts {
+                for captures in taiga_slug_regex.captures_iter(text) {
+                    let slug = captures[1].to_string();
+                    if !is_allowed(&slug) {
+                        names.push((slug.clone(), slug));
+                    }
+                }
+            }
+        }
+        let mut seen = std::collections::HashSet::new();
+        names.retain(|(name, _)| !name.is_empty() && seen.insert(name.to_lowercase()));
+        names.sort_by_key(|name| std::cmp::Reverse(name.0.len()));
+        let names = names.into_iter()
+            .map(|(name, original)| Ok((literal_regex(&name)?, original)))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            secret_regexes,
+            pattern_regexes,
+            aliases,
+            names,
+            placeholders: Vec::new(),
+            assigned: HashMap::new(),
+            counters: HashMap::new(),
+        })
+    }
+
+    /// 是否产生了需要 AI 原样保留的占位符
+    pub fn has_placeholders(&self) -> bool {
+        !self.placeholders.is_empty() || !self.aliases.is_empty()
+    }
+
+    /// 对发送给 AI 的文本进行脱敏
+    pub fn redact(&mut self, text: &str) -> String {
+        let mut result = text.to_string();
+
+        // 1. 密钥/Token，不可逆
+        for regex in &self.secret_regexes {
+            result = regex.replace_all(&result, SECRET_PLACEHOLDER).into_owned();
+        }
+
+        // 2. 代号别名
+        for alias in &self.aliases {
+            result = alias.codename_regex.replace_all(&result, regex::NoExpand(&alias.alias)).into_owned();
+        }
+
+        // 3. 仓库名、项目代号和 Taiga slug（大小写不敏感）
+        let names = self.names.clone();
+        for (regex, original) in names {
+            if regex.is_match(&result) {
+                let placeholder = self.placeholder_for("Proj", &original);
+                result = regex.replace_all(&result, placeholder.as_str()).into_owned();
+            }
+        }
+
+        // 4. 自定义规则
+        let pattern_regexes = self.pattern_regexes.clone();
+        for (regex, label) in pattern_regexes {
+            let matches: Vec<String> = regex.find_iter(&result).map(|m| m.as_str().to_string()).collect();
+            for matched in matches {
+                let placeholder = self.placeholder_for(&label, &matched);
+                result = result.replace(&matched, &placeholder);
+            }
+        }
+
+        result
+    }
+
+    /// 将 AI 输出中的占位符和别名还原为原始内容
+    pub fn restore(&self, text: &str) -> String {
+        let mut result = text.to_string();
+
+        for placeholder in &self.placeholders {
+            result = placeholder.regex.replace_all(&result, regex::NoExpand(&placeholder.original)).into_owned();
+        }
+
+        for alias in &self.aliases {
+            result = alias.alias_regex.replace_all(&result, regex::NoExpand(&alias.codename)).into_owned();
+        }
+
+        result
+    }
+
+    /// 获取原始内容对应的占位符
+    fn placeholder_for(&mut self, label: &str, original: &str) -> String {
+        let key = (label.to_string(), original.to_lowercase());
+        if let Some(placeholder) = self.assigned.get(&key) {
+            return placeholder.clone();
+        }
+
+        let counter = self.counters.entry(label.to_string()).or_insert(0);
+        *counter += 1;
+        let placeholder = format!("{}{}", label, counter);
+        let regex = Regex::new(&format!(r"(?i)(?-u:\b){}(?-u:\b)", regex::escape(&placeholder))).unwrap();
+        let position = self.placeholders.iter()
+            .position(|existing| existing.placeholder.len() < placeholder.len())
+            .unwrap_or(self.placeholders.len());
+        self.placeholders.insert(position, Placeholder {
+            placeholder: placeholder.clone(),
+            original: original.to_string(),
+            regex,
+        });
+        self.assigned.insert(key, placeholder.clone());
+        placeholder
+    }
+}
+
+/// 不区分大小写匹配一段文本的正则
+/// 以 ASCII 字母、数字或下划线开头（结尾）时要求该侧是单词边界，避免匹配到更长的英文名称中；
+/// 以中文等其他字符开头（结尾）时该侧按子串匹配，中文前后通常没有空格，使用单词边界会漏掉
+fn literal_regex(text: &str) -> Result<Regex> {
+    let is_word = |c: Option<char>| c.map(|c| c.is_ascii_alphanumeric() || c == '_').unwrap_or(false);
+    let start = if is_word(text.chars().next()) { r"(?-u:\b)" } else { "" };
+    let end = if is_word(text.chars().next_back()) { r"(?-u:\b)" } else { "" };
+
+    Regex::new(&format!("(?i){}{}{}", start, regex::escape(text), end))
+        .map_err(|e| anyhow!("无法为 {} 创建脱敏规则: {}", text, e))
+}
+
+/// 流式输出的占位符还原：存在占位符时按行缓冲，避免占位符被分块截断后无法还原
+pub struct StreamingRestorer {
+    redactor: Redactor,
+    pending: String,
+}
+
+impl StreamingRestorer {
+    pub fn new(redactor: Redactor) -> Self {
+        Self {
+            redactor,
+            pending: String::new(),
+        }
+    }
+
+    /// 输入一段 AI 输出，返回可以立即发送的已还原文本
+    pub fn push(&mut self, token: &str) -> String {
+        if !self.redactor.has_placeholders() {
+            return token.to_string();
+        }
+
+        self.pending.push_str(token);
+        match self.pending.rfind('\n') {
+            Some(newline) => {
+                let complete: String = self.pending.drain(..=newline).collect();
+                self.redactor.restore(&complete)
+            }
+            None => String::new(),
+        }
+    }
+
+    /// 流结束时还原剩余的文本
+    pub fn finish(&mut self) -> String {
+        let remaining = std::mem::take(&mut self.pending);
+        self.redactor.restore(&remaining)
+    }
+
+    /// 流结束时改用完整输出：丢弃尚未发送的缓冲，返回还原后的完整文本
+    pub fn restore_full(&mut self, content: &str) -> String {
+        self.pending.clear();
+        self.redactor.restore(content)
+    }
+}
+
+/// 站会数据中每个仓库（owner/repo）对应的项目代号
+pub fn project_codes(entries: &[StandupEntry]) -> Vec<(String, String)> {
+    let mut codes: Vec<(String, String)> = Vec::new();
+    for entry in entries {
+        if entry.project_code.is_empty() || codes.iter().any(|(repo, _)| repo == &entry.repo) {
+            continue;
+        }
+        codes.push((entry.repo.clone(), entry.project_code.clone()));
+    }
+    codes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redactor(rules_json: &str) -> Redactor {
+        let rules = RedactionRules::from_json(rules_json).unwrap();
+        let repos = vec!["acme/secret-web".to_string(), "acme/public".to_string()];
+        let codes = vec![("acme/secret-web".to_string(), "SW".to_string())];
+        let texts = vec!["https://tree.taiga.io/project/acme-internal/task/12".to_string()];
+        Redactor::from_sources(&rules, &repos, &codes, &texts).unwrap()
+    }
+
+    #[test]
+    fn round_trips_repo_names_codes_and_taiga_slugs() {
+        let mut redactor = redactor(r#"{"repo_allowlist": ["acme/public"]}"#);
+        let text = "[2]SW#12-修复 secret-web 登录，见 acme-internal；acme/public 不脱敏";
+
+        let redacted = redactor.redact(text);
+        assert!(!redacted.contains("secret-web"));
+        assert!(!redacted.contains("SW#"));
+        assert!(!redacted.contains("acme-internal"));
+        assert!(redacted.contains("acme/public"));
+        assert!(redactor.has_placeholders());
+
+        assert_eq!(redactor.restore(&redacted), text);
+    }
+
+    #[test]
+    fn restores_placeholders_case_insensitively_without_prefix_collisions() {
+        let mut redactor = redactor(r#"{"patterns": [{"pattern": "客户[A-Z]", "label": "Client"}]}"#);
+        let redacted = redactor.redact("客户A 客户B 客户C 客户D 客户E 客户F 客户G 客户H 客户I 客户J");
+        assert!(redacted.contains("Client10"));
+
+        let restored = redactor.restore("client1 和 CLIENT10");
+        assert_eq!(restored, "客户A 和 客户J");
+    }
+
+    #[test]
+    fn aliases_round_trip_and_secrets_stay_redacted() {
+        let mut redactor = redactor(r#"{"aliases": {"Phoenix": "Bird"}}"#);
+        let token = format!("ghp_{}", "a".repeat(36));
+        let text = format!("Phoenix 上线，token {}", token);
+
+        let redacted = redactor.redact(&text);
+        assert_eq!(redacted, format!("Bird 上线，token {}", SECRET_PLACEHOLDER));
+        assert_eq!(redactor.restore(&redacted), format!("Phoenix 上线，token {}", SECRET_PLACEHOLDER));
+    }
+
+    #[test]
+    fn streaming_restorer_restores_placeholders_split_across_tokens() {
+        let mut redactor = redactor(r#"{"repo_allowlist": []}"#);
+        let redacted = redactor.redact("secret-web 完成\n下一行");
+        let mut restorer = StreamingRestorer::new(redactor);
+
+        let (head, tail) = redacted.split_at(3);
+        let mut output = restorer.push(head);
+        output.push_str(&restorer.push(tail));
+        output.push_str(&restorer.finish());
+        assert_eq!(output, "secret-web 完成\n下一行");
+    }
+}
@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+use crate::github_api::StandupEntry;
+
+/// 已发送的站会报告，连同生成报告时的原始数据一起保存
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredReport {
+    #[serde(default)]
+    pub id: Option<i64>,
+    pub login: String,         // GitHub 用户名
+    pub report_date: String,   // 成员所在时区的日期 YYYY-MM-DD
+    pub report: String,        // 最终发送的报告
+    pub raw_data: String,      // 发送给 AI 之前的原始数据（未脱敏）
+    pub entries: String,       // 结构化的 PR 数据，JSON 数组
+    #[serde(default)]
+    pub created_at: Option<String>,
+}
+
+impl StoredReport {
+    pub fn new(login: &str, report_date: &str, report: &str, raw_data: &str, entries: &[StandupEntry]) -> Self {
+        Self {
+            id: None,
+            login: login.to_string(),
+            report_date: report_date.to_string(),
+            report: report.to_string(),
+            raw_data: raw_data.to_string(),
+            entries: serde_json::to_string(entries).unwrap_or_else(|_| "[]".to_string()),
+            created_at: None,
+        }
+    }
+
+    /// 解析结构化的 PR 数据
+    pub fn parsed_entries(&self) -> Vec<StandupEntry> {
+        serde_json::from_str(&self.entries).unwrap_or_default()
+    }
+
+    /// 接口返回的 JSON，entries 展开为数组
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "id": self.id,
+            "user": self.login,
+            "date": self.report_date,
+            "report": self.report,
+            "raw_data": self.raw_data,
+            "entries": self.parsed_entries(),
+            "created_at": self.created_at
+        })
+    }
+}
@@ -0,0 +1,354 @@
+use std::collections::{HashMap, HashSet};
+use regex::Regex;
+
+use crate::github_api::StandupData;
+use crate::locale::Locale;
+
+/// AI 输出与原始数据不一致的类型
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiscrepancyKind {
+    /// 任务编号存在，但天数与数据库记录不一致
+    DayCountMismatch { task_id: String, found: i32, expected: i32 },
+    /// 原始数据中不存在的任务编号
+    UnknownTaskId(String),
+    /// 原始数据中不存在的 PR 或仓库（owner/repo#编号 或 GitHub 链接）
+    UnknownPullRequest(String),
+    /// 未关联任务的行使用了原始数据中不存在的天数
+    UnverifiedDayCount(i32),
+}
+
+/// 单条不一致记录
+#[derive(Debug, Clone)]
+pub struct Discrepancy {
+    pub line: usize,
+    pub text: String,
+    pub kind: DiscrepancyKind,
+}
+
+impl Discrepancy {
+    /// 按报告语言描述不一致之处，用于日志和纠错说明
+    pub fn describe(&self, locale: Locale) -> String {
+        let texts = locale.texts();
+        let location = format!("{}{}{}{}{}",
+            texts.discrepancy_line_prefix, self.line, texts.discrepancy_line_suffix, self.text, texts.discrepancy_text_suffix);
+        let problem = match &self.kind {
+            DiscrepancyKind::DayCountMismatch { task_id, found, expected } => format!("{}{}{}[{}]{}[{}]",
+                texts.discrepancy_task, task_id, texts.discrepancy_days_found, found, texts.discrepancy_days_expected, expected),
+            DiscrepancyKind::UnknownTaskId(task_id) => format!("{}{}{}",
+                texts.discrepancy_task, task_id, texts.discrepancy_unknown_task),
+            DiscrepancyKind::UnknownPullRequest(reference) => format!("{}{}{}",
+                texts.discrepancy_pr, reference, texts.discrepancy_unknown_task),
+            DiscrepancyKind::UnverifiedDayCount(found) => format!("{}[{}]{}",
+                texts.discrepancy_days, found, texts.discrepancy_unverified_days),
+        };
+        format!("{}{}", location, problem)
+    }
+}
+
+/// 校验结果：修正后的报告和发现的不一致
+#[derive(Debug, Clone)]
+pub struct ValidationResult {
+    pub corrected: String,
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+impl ValidationResult {
+    /// 是否与原始数据完全一致
+    pub fn is_valid(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+
+    /// 是否存在无法直接修正、需要重新生成的问题（编造的任务编号、PR 或仓库）
+    pub fn needs_regeneration(&self) -> bool {
+        self.discrepancies.iter()
+            .any(|d| matches!(d.kind, DiscrepancyKind::UnknownTaskId(_) | DiscrepancyKind::UnknownPullRequest(_)))
+    }
+}
+
+/// AI 报告校验器：检查每行的 [天数]、#编号和 PR 引用是否来自原始数据
+pub struct ReportValidator {
+    /// Taiga 编号 → 数据库中的累积天数（没有天数记录的编号为空列表）
+    task_days: HashMap<String, Vec<i32>>,
+    /// 原始数据中出现过的所有天数
+    known_days: HashSet<i32>,
+    /// 原始数据中的仓库（小写 owner/repo）
+    repos: HashSet<String>,
+    /// 原始数据中的 PR 和任务引用：(小写 owner/repo, 编号)
+    references: HashSet<(String, String)>,
+    line_regex: Regex,
+    task_id_regex: Regex,
+    pr_regex: Regex,
+}
+
+impl ReportValidator {
+    /// 基于 generate_standup_report 整理的数据创建校验器
+    pub fn new(data: &StandupData) -> Self {
+        let pull_number_regex = Regex::new(r"/pull/(\d+)").unwrap();
+        let mut task_days: HashMap<String, Vec<i32>> = HashMap::new();
+        let mut repos = HashSet::new();
+        let mut references = HashSet::new();
+        for entry in &data.entries {
+            let repo = entry.repo.to_lowercase();
+            if let Some(captures) = pull_number_regex.captures(&entry.html_url) {
+                references.insert((repo.clone(), captures[1].to_string()));
+            }
+            if let Some(taiga_id) = &entry.taiga_id {
+                references.insert((repo.clone(), taiga_id.clone()));
+            }
+            repos.insert(repo);
+
+            if let Some(taiga_id) = &entry.taiga_id {
+                let days = task_days.entry(taiga_id.clone()).or_default();
+                if let Some(entry_days) = entry.days {
+                    days.push(entry_days);
+                }
+            }
+        }
+
+        // 数据库中没有记录的任务默认为第一天
+        let mut known_days: HashSet<i32> = data.taiga_days_map.values().copied().collect();
+        known_days.insert(1);
+
+        Self {
+            task_days,
+            known_days,
+            repos,
+            references,
+            line_regex: Regex::new(r"^(\s*)\[(\d+)\]").unwrap(),
+            task_id_regex: Regex::new(r"#(\d+)\b").unwrap(),
+            pr_regex: Regex::new(r"https?://github\.com/([A-Za-z0-9_.-]+/[A-Za-z0-9_.-]+)(?:/pull/(\d+)\b)?|([A-Za-z0-9_.-]+/[A-Za-z0-9_.-]+)#(\d+)\b").unwrap(),
+        }
+    }
+
+    /// 校验 AI 输出，修正天数并移除编造的任务编号和 PR 引用
+    pub fn validate(&self, report: &str) -> ValidationResult {
+        let mut discrepancies = Vec::new();
+        let mut corrected_lines = Vec::new();
+
+        for (index, line) in report.lines().enumerate() {
+            let line_number = index + 1;
+            let captures = match self.line_regex.captures(line) {
+                Some(captures) => captures,
+                None => {
+                    let mut removed = Vec::new();
+                    self.check_pull_requests(line_number, line, line, &mut discrepancies, &mut removed);
+                    corrected_lines.push(remove_spans(line, removed));
+                    continue;
+                }
+            };
+
+            let found: i32 = captures[2].parse().unwrap_or(0);
+            let prefix_len = captures[0].len();
+            let indent = captures[1].to_string();
+            let content = &line[prefix_len..];
+            let mut expected = found;
+            // 需要从该行移除的编造内容（字节区间）
+            let mut removed: Vec<std::ops::Range<usize>> = Vec::new();
+
+            let pr_spans = self.check_pull_requests(line_number, line, content, &mut discrepancies, &mut removed);
+
+            let mut verified = false;
+            for captures in self.task_id_regex.captures_iter(content) {
+                let span = captures.get(0).map(|m| m.range()).unwrap_or_default();
+                if pr_spans.iter().any(|pr_span| pr_span.start <= span.start && span.end <= pr_span.end) {
+                    continue;
+                }
+
+                let task_id = captures[1].to_string();
+                match self.task_days.get(&task_id) {
+                    Some(days) => {
+                        verified = true;
+                        if !days.is_empty() && !days.contains(&found) {
+                            expected = days[0];
+                            discrepancies.push(Discrepancy {
+                                line: line_number,
+                                text: line.trim().to_string(),
+                                kind: DiscrepancyKind::DayCountMismatch {
+                                    task_id,
+                                    found,
+                                    expected,
+                                },
+                            });
+                        }
+                    }
+                    None => {
+                        discrepancies.push(Discrepancy {
+                            line: line_number,
+                            text: line.trim().to_string(),
+                            kind: DiscrepancyKind::UnknownTaskId(task_id),
+                        });
+                        removed.push(span);
+                    }
+                }
+            }
+
+            if !verified && !self.known_days.contains(&found) {
+                discrepancies.push(Discrepancy {
+                    line: line_number,
+                    text: line.trim().to_string(),
+                    kind: DiscrepancyKind::UnverifiedDayCount(found),
+                });
+                expected = 1;
+            }
+
+            corrected_lines.push(format!("{}[{}]{}", indent, expected, remove_spans(content, removed)));
+        }
+
+        ValidationResult {
+            corrected: corrected_lines.join("\n"),
+            discrepancies,
+        }
+    }
+
+    /// 检查 PR 链接和 owner/repo#编号 引用是否来自原始数据，编造的引用记入 removed
+    /// 返回所有引用的区间，其中的 #编号 不再按任务编号校验
+    fn check_pull_requests(
+        &self,
+        line_number: usize,
+        line: &str,
+        content: &str,
+        discrepancies: &mut Vec<Discrepancy>,
+        removed: &mut Vec<std::ops::Range<usize>>,
+    ) -> Vec<std::ops::Range<usize>> {
+        let mut spans = Vec::new();
+        for captures in self.pr_regex.captures_iter(content) {
+            let span = captures.get(0).map(|m| m.range()).unwrap_or_default();
+            let repo = captures.get(1).or_else(|| captures.get(3)).map(|m| m.as_str()).unwrap_or("");
+            let number = captures.get(2).or_else(|| captures.get(4)).map(|m| m.as_str());
+            let repo_key = repo.trim_end_matches(".git").to_lowercase();
+            let known = match number {
+                Some(number) => self.references.contains(&(repo_key, number.to_string())),
+                None => self.repos.contains(&repo_key),
+            };
+            if !known {
+                let reference = match number {
+                    Some(number) => format!("{}#{}", repo, number),
+                    None => repo.to_string(),
+                };
+                discrepancies.push(Discrepancy {
+                    line: line_number,
+                    text: line.trim().to_string(),
+                    kind: DiscrepancyKind::UnknownPullRequest(reference),
+                });
+                removed.push(span.clone());
+            }
+            spans.push(span);
+        }
+        spans
+    }
+
+    /// 生成重新生成时附加给 AI 的纠错说明
+    pub fn feedback_prompt(&self, discrepancies: &[Discrepancy], locale: Locale) -> String {
+        let texts = locale.texts();
+        let mut feedback = String::from(texts.feedback_header);
+        for discrepancy in discrepancies {
+            feedback.push_str(&format!("- {}\n", discrepancy.describe(locale)));
+        }
+        feedback.push_str(texts.feedback_footer);
+        feedback
+    }
+}
+
+/// 从文本中移除指定的字节区间
+fn remove_spans(text: &str, mut spans: Vec<std::ops::Range<usize>>) -> String {
+    spans.sort_by_key(|span| span.start);
+    let mut result = String::with_capacity(text.len());
+    let mut position = 0;
+    for span in spans {
+        if span.start >= position {
+            result.push_str(&text[position..span.start]);
+            position = span.end;
+        }
+    }
+    result.push_str(&text[position..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github_api::{PrStatus, StandupEntry};
+
+    fn entry(repo: &str, pr_number: u32, taiga_id: Option<&str>, days: Option<i32>) -> StandupEntry {
+        StandupEntry {
+            title: "修复登录".to_string(),
+            repo: repo.to_string(),
+            status: PrStatus::Merged,
+            taiga_id: taiga_id.map(str::to_string),
+            task_key: taiga_id.map(|id| format!("acme#{}", id)),
+            days,
+            task_done: false,
+            project_code: repo.rsplit('/').next().unwrap_or(repo).to_string(),
+            work_summary: String::new(),
+            html_url: format!("https://github.com/{}/pull/{}", repo, pr_number),
+        }
+    }
+
+    fn validator() -> ReportValidator {
+        let data = StandupData {
+            report: String::new(),
+            entries: vec![
+                entry("acme/web", 7, Some("123"), Some(2)),
+                entry("acme/api", 9, None, None),
+            ],
+            taiga_days_map: HashMap::from([("acme#123".to_string(), 2)]),
+        };
+        ReportValidator::new(&data)
+    }
+
+    #[test]
+    fn accepts_report_matching_raw_data() {
+        let result = validator().validate("今日工作：\n[2]web#123-修复登录\n[1]整理文档");
+        assert!(result.is_valid());
+        assert_eq!(result.corrected, "今日工作：\n[2]web#123-修复登录\n[1]整理文档");
+    }
+
+    #[test]
+    fn corrects_day_count_mismatch() {
+        let result = validator().validate("[5]web#123-修复登录");
+        assert_eq!(result.corrected, "[2]web#123-修复登录");
+        assert_eq!(result.discrepancies[0].kind, DiscrepancyKind::DayCountMismatch {
+            task_id: "123".to_string(),
+            found: 5,
+            expected: 2,
+        });
+        assert!(!result.needs_regeneration());
+    }
+
+    #[test]
+    fn removes_only_the_exact_unknown_task_id() {
+        let result = validator().validate("[2]web#12-修复登录 #123");
+        assert_eq!(result.corrected, "[2]web-修复登录 #123");
+        assert_eq!(result.discrepancies.len(), 1);
+        assert_eq!(result.discrepancies[0].kind, DiscrepancyKind::UnknownTaskId("12".to_string()));
+        assert!(result.needs_regeneration());
+    }
+
+    #[test]
+    fn rejects_pull_requests_and_repos_missing_from_input() {
+        let result = validator().validate("[1]整理文档 acme/api#9 acme/api#10 https://github.com/acme/web/pull/8\n参考 https://github.com/other/repo");
+        assert_eq!(result.corrected, "[1]整理文档 acme/api#9  \n参考 ");
+        let unknown: Vec<_> = result.discrepancies.iter().map(|d| d.kind.clone()).collect();
+        assert_eq!(unknown, vec![
+            DiscrepancyKind::UnknownPullRequest("acme/api#10".to_string()),
+            DiscrepancyKind::UnknownPullRequest("acme/web#8".to_string()),
+            DiscrepancyKind::UnknownPullRequest("other/repo".to_string()),
+        ]);
+        assert!(result.needs_regeneration());
+    }
+
+    #[test]
+    fn flags_unverified_day_count_without_task() {
+        let result = validator().validate("[4]整理文档");
+        assert_eq!(result.corrected, "[1]整理文档");
+        assert_eq!(result.discrepancies[0].kind, DiscrepancyKind::UnverifiedDayCount(4));
+    }
+
+    #[test]
+    fn feedback_prompt_lists_each_discrepancy() {
+        let validator = validator();
+        let result = validator.validate("[2]web#12-修复登录");
+        let feedback = validator.feedback_prompt(&result.discrepancies, Locale::default());
+        assert!(feedback.contains("第 1 行「[2]web#12-修复登录」：任务 #12 不在原始数据中"));
+    }
+}
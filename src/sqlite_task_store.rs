@@ -0,0 +1,237 @@
+use std::collections::HashSet;
+use chrono::NaiveDate;
+use anyhow::{Result, anyhow};
+use futures::future::LocalBoxFuture;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::database::{TaigaTaskRecord, TaskStatus, TaskStatusSource, TASK_COLUMNS};
+use crate::leave::{leave_days, LeaveRecord};
+use crate::migrations::{MigrationStep, MIGRATIONS};
+use crate::task_store::TaskStore;
+
+/// 基于 SQLite 的任务存储（仅本地构建），表结构与 D1 使用相同的迁移
+pub struct SqliteTaskStore {
+    conn: Connection,
+}
+
+impl SqliteTaskStore {
+    /// 创建内存中的 SQLite 数据库并执行迁移
+    pub fn open_in_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory()
+            .map_err(|e| anyhow!("创建 SQLite 数据库失败: {:?}", e))?;
+        Self::with_connection(conn)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self> {
+        let store = Self { conn };
+        store.migrate()?;
+        Ok(store)
+    }
+
+    /// 与 D1 相同，只执行 schema_migrations 中尚未记录的迁移，每个迁移在一个事务中执行
+    fn migrate(&self) -> Result<()> {
+        self.conn.execute_batch(r#"
+            CREATE TABLE IF NOT EXISTS schema_migrations (
+                version INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                applied_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )
+        "#).map_err(|e| anyhow!("创建迁移记录表失败: {:?}", e))?;
+
+        let current_version: i32 = self.conn
+            .query_row("SELECT COALESCE(MAX(version), 0) FROM schema_migrations", [], |row| row.get(0))
+            .map_err(|e| anyhow!("查询数据库版本失败: {:?}", e))?;
+
+        for migration in MIGRATIONS.iter().filter(|migration| migration.version > current_version) {
+            let mut statements = Vec::new();
+            for step in migration.steps {
+                match step {
+                    MigrationStep::Sql(sql) => statements.push(sql.to_string()),
+                    MigrationStep::AddColumn { table, column, definition } => {
+                        if !self.has_column(table, column)? {
+                            statements.push(format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition));
+                        }
+                    }
+                }
+            }
+
+            self.transaction(|conn| {
+                for sql in &statements {
+                    conn.execute_batch(sql)?;
+                }
+                conn.execute("INSERT INTO schema_migrations (version, name) VALUES (?1, ?2)", params![migration.version, migration.name])?;
+                Ok(())
+            }).map_err(|e| anyhow!("执行数据库迁移 {} ({}) 失败: {:?}", migration.version, migration.name, e))?;
+        }
+        Ok(())
+    }
+
+    fn has_column(&self, table: &str, column: &str) -> Result<bool> {
+        let mut statement = self.conn.prepare(&format!("PRAGMA table_info({})", table))
+            .map_err(|e| anyhow!("查询 {} 表结构失败: {:?}", table, e))?;
+        let names = statement.query_map([], |row| row.get::<_, String>("name"))
+            .map_err(|e| anyhow!("查询 {} 表结构失败: {:?}", table, e))?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(|e| anyhow!("解析 {} 表结构失败: {:?}", table, e))?;
+        Ok(names.iter().any(|name| name == column))
+    }
+
+    /// 在事务中执行，失败时回滚
+    fn transaction<T>(&self, f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> rusqlite::Result<T> {
+        self.conn.execute_batch("BEGIN")?;
+        match f(&self.conn) {
+            Ok(value) => {
+                self.conn.execute_batch("COMMIT")?;
+                Ok(value)
+            }
+            Err(e) => {
+                let _ = self.conn.execute_batch("ROLLBACK");
+                Err(e)
+            }
+        }
+    }
+
+    /// 添加请假记录
+    pub fn add_leave(&self, leave: &LeaveRecord) -> Result<()> {
+        leave.date_range()?;
+        self.conn.execute(
+            "INSERT INTO leave (login, start_date, end_date, reason, source) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![leave.login, leave.start_date, leave.end_date, leave.reason, leave.source],
+        ).map_err(|e| anyhow!("添加请假记录失败: {:?}", e))?;
+        Ok(())
+    }
+}
+
+impl TaskStore for SqliteTaskStore {
+    fn get_task<'b>(&'b self, task_key: &'b str) -> LocalBoxFuture<'b, Result<Option<TaigaTaskRecord>>> {
+        Box::pin(async move {
+            self.conn.query_row(
+                &format!("SELECT {} FROM taiga_tasks WHERE task_key = ?1", TASK_COLUMNS),
+                params![task_key],
+                |row| Ok(TaigaTaskRecord {
+                    task_key: row.get(0)?,
+                    first_seen_date: row.get(1)?,
+                    last_seen_date: row.get(2)?,
+                    total_days: row.get(3)?,
+                    status: TaskStatus::parse(&row.get::<_, String>(4)?).unwrap_or_default(),
+                    extra_days: row.get(5)?,
+                    status_source: TaskStatusSource::parse(&row.get::<_, String>(6)?).unwrap_or_default(),
+                }),
+            )
+            .optional()
+            .map_err(|e| anyhow!("查询任务记录失败: {:?}", e))
+        })
+    }
+
+    fn save_task<'b>(&'b self, record: &'b TaigaTaskRecord) -> LocalBoxFuture<'b, Result<()>> {
+        Box::pin(async move {
+            let upsert_sql = format!(r#"
+                INSERT INTO taiga_tasks ({})
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                ON CONFLICT(task_key) DO UPDATE SET
+                    first_seen_date = excluded.first_seen_date,
+                    last_seen_date = excluded.last_seen_date,
+                    total_days = excluded.total_days,
+                    status = excluded.status,
+                    extra_days = excluded.extra_days,
+                    status_source = excluded.status_source,
+                    updated_at = CURRENT_TIMESTAMP
+            "#, TASK_COLUMNS);
+
+            self.conn.execute(
+                &upsert_sql,
+                params![
+                    record.task_key,
+                    record.first_seen_date,
+                    record.last_seen_date,
+                    record.total_days,
+                    record.status.as_str(),
+                    record.extra_days,
+                    record.status_source.as_str()
+                ],
+            ).map_err(|e| anyhow!("保存任务记录失败: {:?}", e))?;
+            Ok(())
+        })
+    }
+
+    fn record_activity<'b>(&'b self, task_key: &'b str, dates: &'b [&'b str]) -> LocalBoxFuture<'b, Result<()>> {
+        Box::pin(async move {
+            for date in dates {
+                self.conn.execute(
+                    "INSERT OR IGNORE INTO task_activity (task_key, activity_date) VALUES (?1, ?2)",
+                    params![task_key, date],
+                ).map_err(|e| anyhow!("记录任务活跃日期失败: {:?}", e))?;
+            }
+            Ok(())
+        })
+    }
+
+    fn count_active_days<'b>(&'b self, task_key: &'b str) -> LocalBoxFuture<'b, Result<i32>> {
+        Box::pin(async move {
+            self.conn.query_row(
+                "SELECT COUNT(DISTINCT activity_date) FROM task_activity WHERE task_key = ?1",
+                params![task_key],
+                |row| row.get(0),
+            ).map_err(|e| anyhow!("统计任务活跃天数失败: {:?}", e))
+        })
+    }
+
+    fn leave_days<'b>(&'b self, login: &'b str, start_date: &'b str, end_date: &'b str) -> LocalBoxFuture<'b, Result<HashSet<NaiveDate>>> {
+        Box::pin(async move {
+            let mut statement = self.conn.prepare(
+                "SELECT login, start_date, end_date, reason, source FROM leave WHERE login = ?1 AND start_date <= ?3 AND end_date >= ?2",
+            ).map_err(|e| anyhow!("查询请假记录失败: {:?}", e))?;
+
+            let leaves = statement.query_map(params![login, start_date, end_date], |row| {
+                Ok(LeaveRecord {
+                    id: None,
+                    login: row.get(0)?,
+                    start_date: row.get(1)?,
+                    end_date: row.get(2)?,
+                    reason: row.get(3)?,
+                    source: row.get(4)?,
+                })
+            })
+            .map_err(|e| anyhow!("查询请假记录失败: {:?}", e))?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(|e| anyhow!("解析请假记录失败: {:?}", e))?;
+
+            Ok(leave_days(&leaves))
+        })
+    }
+
+    fn archive_tasks<'b>(&'b self, cutoff: &'b str) -> LocalBoxFuture<'b, Result<usize>> {
+        Box::pin(async move {
+            let archive_sql = format!(
+                "INSERT OR REPLACE INTO archived_tasks ({0}) SELECT {0} FROM taiga_tasks WHERE last_seen_date < ?1",
+                TASK_COLUMNS
+            );
+
+            self.transaction(|conn| {
+                conn.execute(&archive_sql, params![cutoff])?;
+                conn.execute("DELETE FROM taiga_tasks WHERE last_seen_date < ?1", params![cutoff])
+            })
+            .map_err(|e| anyhow!("归档旧任务记录失败: {:?}", e))
+        })
+    }
+
+    fn restore_task<'b>(&'b self, task_key: &'b str) -> LocalBoxFuture<'b, Result<Option<TaigaTaskRecord>>> {
+        Box::pin(async move {
+            let restore_sql = format!(
+                "INSERT OR IGNORE INTO taiga_tasks ({0}) SELECT {0} FROM archived_tasks WHERE task_key = ?1",
+                TASK_COLUMNS
+            );
+
+            let restored = self.transaction(|conn| {
+                conn.execute(&restore_sql, params![task_key])?;
+                conn.execute("DELETE FROM archived_tasks WHERE task_key = ?1", params![task_key])
+            })
+            .map_err(|e| anyhow!("恢复归档任务失败: {:?}", e))?;
+
+            if restored == 0 {
+                return Ok(None);
+            }
+            self.get_task(task_key).await
+        })
+    }
+}
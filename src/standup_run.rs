@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+/// 失败的运行最多尝试的次数（含首次运行）
+pub const MAX_RUN_ATTEMPTS: i32 = 3;
+
+/// 运行中的记录超过该时间未更新，视为中断，可以重新运行
+pub const STALE_RUNNING_MINUTES: i64 = 10;
+
+/// 每日站会报告运行状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunStatus {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+impl RunStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RunStatus::Running => "running",
+            RunStatus::Succeeded => "succeeded",
+            RunStatus::Failed => "failed",
+        }
+    }
+}
+
+/// 成员某一天的站会报告运行记录，按 (login, run_date) 唯一
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StandupRun {
+    pub login: String,             // GitHub 用户名
+    pub run_date: String,          // 成员所在时区的日期 YYYY-MM-DD
+    pub status: RunStatus,
+    pub report: Option<String>,    // 发送的报告内容
+    pub channels: Option<String>,  // 已发送的渠道，JSON 数组，例如 ["feishu"]
+    pub error: Option<String>,     // 最近一次失败的原因
+    pub attempts: i32,             // 已尝试次数
+    pub updated_at: String,        // UTC 时间 YYYY-MM-DD HH:MM:SS
+}
+
+/// 运行中但长时间未更新，说明上次运行被中断
+fn is_stale(updated_at: &str, now: DateTime<Utc>) -> bool {
+    match NaiveDateTime::parse_from_str(updated_at, "%Y-%m-%d %H:%M:%S") {
+        Ok(updated_at) => now.naive_utc() - updated_at > chrono::Duration::minutes(STALE_RUNNING_MINUTES),
+        Err(_) => true,
+    }
+}
+
+/// 周报、月报和团队日报是否可以（重新）运行：未成功、未在运行中，且尝试次数未超过上限
+fn can_run(status: RunStatus, attempts: i32, updated_at: &str, now: DateTime<Utc>) -> bool {
+    match status {
+        RunStatus::Succeeded => false,
+        RunStatus::Running => is_stale(updated_at, now) && attempts < MAX_RUN_ATTEMPTS,
+        RunStatus::Failed => attempts < MAX_RUN_ATTEMPTS,
+    }
+}
+
+impl StandupRun {
+    /// 运行中但长时间未更新，说明上次运行被中断
+    pub fn is_stale(&self, now: DateTime<Utc>) -> bool {
+        is_stale(&self.updated_at, now)
+    }
+
+    /// 是否需要在定时任务中自动重试
+    pub fn needs_retry(&self, now: DateTime<Utc>) -> bool {
+        let interrupted = self.status == RunStatus::Running && self.is_stale(now);
+        (self.status == RunStatus::Failed || interrupted) && self.attempts < MAX_RUN_ATTEMPTS
+    }
+
+    /// 是否已经结束（成功，或失败且不会再自动重试）
+    pub fn is_finished(&self, now: DateTime<Utc>) -> bool {
+        match self.status {
+            RunStatus::Succeeded => true,
+            RunStatus::Running => false,
+            RunStatus::Failed => !self.needs_retry(now),
+        }
+    }
+}
+
+/// 成员某个周期的周报或月报运行记录，按 (login, period, period_end) 唯一
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SummaryRun {
+    pub login: String,             // GitHub 用户名
+    pub period: String,            // weekly 或 monthly
+    pub period_end: String,        // 汇总的最后一天 YYYY-MM-DD
+    pub status: RunStatus,
+    pub error: Option<String>,     // 最近一次失败的原因
+    pub attempts: i32,             // 已尝试次数
+    pub updated_at: String,        // UTC 时间 YYYY-MM-DD HH:MM:SS
+}
+
+impl SummaryRun {
+    /// 定时任务是否可以（重新）运行
+    pub fn can_run(&self, now: DateTime<Utc>) -> bool {
+        can_run(self.status, self.attempts, &self.updated_at, now)
+    }
+}
+
+/// 某一天的团队日报运行记录，按 digest_date 唯一
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestRun {
+    pub digest_date: String,       // 团队日报时区的日期 YYYY-MM-DD
+    pub status: RunStatus,
+    pub report: Option<String>,    // 发送的团队日报内容
+    pub error: Option<String>,     // 最近一次失败的原因
+    pub attempts: i32,             // 已尝试次数
+    pub updated_at: String,        // UTC 时间 YYYY-MM-DD HH:MM:SS
+}
+
+impl DigestRun {
+    /// 定时任务是否可以（重新）运行
+    pub fn can_run(&self, now: DateTime<Utc>) -> bool {
+        can_run(self.status, self.attempts, &self.updated_at, now)
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::{BTreeMap, HashSet};
+use chrono::{Datelike, NaiveDate};
+
+use crate::database::TaigaTaskRecord;
+use crate::github_api::PrStatus;
+use crate::locale::Locale;
+use crate::report_history::StoredReport;
+use crate::redaction::project_codes;
+
+/// 汇总报告的周期
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SummaryPeriod {
+    Weekly,
+    Monthly,
+}
+
+impl SummaryPeriod {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "weekly" | "week" => Some(SummaryPeriod::Weekly),
+            "monthly" | "month" => Some(SummaryPeriod::Monthly),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SummaryPeriod::Weekly => "weekly",
+            SummaryPeriod::Monthly => "monthly",
+        }
+    }
+
+    /// 在 date 当天需要发送的汇总报告：周五发送周报，每月最后一天发送月报
+    pub fn due_on(date: NaiveDate) -> Vec<Self> {
+        let mut periods = Vec::new();
+        if date.weekday() == chrono::Weekday::Fri {
+            periods.push(SummaryPeriod::Weekly);
+        }
+        if date.succ_opt().map(|next| next.month() != date.month()).unwrap_or(true) {
+            periods.push(SummaryPeriod::Monthly);
+        }
+        periods
+    }
+
+    /// 包含 date 的周期范围：周报为本周一到 date，月报为本月 1 日到 date
+    pub fn range(&self, date: NaiveDate) -> (NaiveDate, NaiveDate) {
+        let start = match self {
+            SummaryPeriod::Weekly => date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64),
+            SummaryPeriod::Monthly => date.with_day(1).unwrap_or(date),
+        };
+        (start, date)
+    }
+
+    /// 飞书消息标题
+    pub fn title(&self, locale: Locale) -> &'static str {
+        let texts = locale.texts();
+        match self {
+            SummaryPeriod::Weekly => texts.weekly_title,
+            SummaryPeriod::Monthly => texts.monthly_title,
+        }
+    }
+
+    fn data_header(&self, locale: Locale) -> &'static str {
+        let texts = locale.texts();
+        match self {
+            SummaryPeriod::Weekly => texts.weekly_data_header,
+            SummaryPeriod::Monthly => texts.monthly_data_header,
+        }
+    }
+
+    fn prompt(&self, locale: Locale) -> &'static str {
+        let texts = locale.texts();
+        match self {
+            SummaryPeriod::Weekly => texts.weekly_prompt,
+            SummaryPeriod::Monthly => texts.monthly_prompt,
+        }
+    }
+}
+
+/// 汇总报告的原始数据，以及创建脱敏器所需的仓库名、项目代号和文本
+pub struct SummaryData {
+    pub report: String,
+    pub repos: Vec<String>,
+    pub codes: Vec<(String, String)>,
+    pub texts: Vec<String>,
+}
+
+/// 报告中出现过的 Taiga 任务键
+pub fn task_keys(reports: &[StoredReport]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    reports.iter()
+        .flat_map(|report| report.parsed_entries())
+        .filter_map(|entry| entry.task_key)
+        .filter(|key| seen.insert(key.clone()))
+        .collect()
+}
+
+/// 汇总时间段内的每日报告、已合并的 PR 和任务天数，生成发送给 AI 的原始数据
+pub fn build_summary_data(
+    period: SummaryPeriod,
+    from: NaiveDate,
+    to: NaiveDate,
+    reports: &[StoredReport],
+    tasks: &[TaigaTaskRecord],
+    locale: Locale,
+) -> SummaryData {
+    let texts = locale.texts();
+    let mut report = String::new();
+
+    report.push_str(&format!("{}\n", period.data_header(locale)));
+    report.push_str(&format!("{}{} ~ {}\n\n", texts.period_range, from, to));
+
+    // 同一天多次发送时只保留最后一次（报告按日期和创建时间排序）
+    let mut daily_reports: BTreeMap<&str, &StoredReport> = BTreeMap::new();
+    for stored in reports {
+        daily_reports.insert(stored.report_date.as_str(), stored);
+    }
+
+    report.push_str(&format!("{}\n\n", texts.daily_reports_header));
+    if daily_reports.is_empty() {
+        report.push_str(&format!("{}\n\n", texts.no_reports));
+    }
+    for (date, stored) in &daily_reports {
+        report.push_str(&format!("### {}\n{}\n\n", date, stored.report.trim()));
+    }
+
+    let mut repos = Vec::new();
+    let mut entries = Vec::new();
+    let mut source_texts = Vec::new();
+    let mut seen_urls = HashSet::new();
+    let mut merged_lines = Vec::new();
+    for stored in daily_reports.values() {
+        source_texts.push(stored.raw_data.clone());
+        for entry in stored.parsed_entries() {
+            if !repos.contains(&entry.repo) {
+                repos.push(entry.repo.clone());
+            }
+            if entry.status == PrStatus::Merged && seen_urls.insert(entry.html_url.clone()) {
+                merged_lines.push(format!("- [{}] {}", entry.repo, entry.title));
+            }
+            entries.push(entry);
+        }
+    }
+
+    report.push_str(&format!("{}\n\n", texts.merged_prs_header));
+    if merged_lines.is_empty() {
+        report.push_str(&format!("{}\n", texts.no_merged_prs));
+    } else {
+        report.push_str(&merged_lines.join("\n"));
+        report.push('\n');
+    }
+    report.push('\n');
+
+    if !tasks.is_empty() {
+        report.push_str(&format!("{}\n\n", texts.tasks_header));
+        for task in tasks {
+            report.push_str(&format!(
+                "- {}{}{}{} ({} ~ {})\n",
+                task.task_key, texts.days_prefix, task.total_days, texts.days_suffix,
+                task.first_seen_date, task.last_seen_date
+            ));
+        }
+        report.push('\n');
+    }
+
+    report.push_str(&format!("{}\n\n{}", texts.ai_guide_header, period.prompt(locale)));
+
+    SummaryData {
+        report,
+        repos,
+        codes: project_codes(&entries),
+        texts: source_texts,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn weekly_is_due_on_fridays_and_monthly_on_the_last_day() {
+        assert_eq!(SummaryPeriod::due_on(date("2025-10-17")), vec![SummaryPeriod::Weekly]);
+        assert_eq!(SummaryPeriod::due_on(date("2025-10-16")), vec![]);
+        assert_eq!(SummaryPeriod::due_on(date("2025-09-30")), vec![SummaryPeriod::Monthly]);
+        assert_eq!(SummaryPeriod::due_on(date("2025-10-31")), vec![SummaryPeriod::Weekly, SummaryPeriod::Monthly]);
+        assert_eq!(SummaryPeriod::due_on(date("2024-02-29")), vec![SummaryPeriod::Monthly]);
+        assert_eq!(SummaryPeriod::due_on(date("2025-12-31")), vec![SummaryPeriod::Monthly]);
+    }
+
+    #[test]
+    fn range_starts_on_monday_or_the_first_of_the_month() {
+        assert_eq!(SummaryPeriod::Weekly.range(date("2025-10-17")), (date("2025-10-13"), date("2025-10-17")));
+        assert_eq!(SummaryPeriod::Weekly.range(date("2025-10-13")), (date("2025-10-13"), date("2025-10-13")));
+        assert_eq!(SummaryPeriod::Weekly.range(date("2025-10-02")), (date("2025-09-29"), date("2025-10-02")));
+        assert_eq!(SummaryPeriod::Monthly.range(date("2025-10-31")), (date("2025-10-01"), date("2025-10-31")));
+    }
+
+    #[test]
+    fn parses_period_names() {
+        assert_eq!(SummaryPeriod::parse(" Week "), Some(SummaryPeriod::Weekly));
+        assert_eq!(SummaryPeriod::parse("monthly"), Some(SummaryPeriod::Monthly));
+        assert_eq!(SummaryPeriod::parse("daily"), None);
+    }
+}
@@ -0,0 +1,38 @@
+use std::collections::HashMap;
+use anyhow::{Result, anyhow};
+
+/// 仓库到 Taiga 项目的映射，来自 TAIGA_PROJECTS 环境变量（JSON），例如
+/// {"zenai-international/soraka_flutter": "zenai-international-soraka"}
+/// 只有配置了映射的仓库，PR 中的 #N 才可能被当作 Taiga 任务编号
+#[derive(Debug, Clone, Default)]
+pub struct TaigaProjects {
+    repos: HashMap<String, String>,
+}
+
+impl TaigaProjects {
+    /// 从 JSON 字符串解析映射，仓库名不区分大小写
+    pub fn from_json(json: &str) -> Result<Self> {
+        let repos: HashMap<String, String> = serde_json::from_str(json)
+            .map_err(|e| anyhow!("解析 Taiga 项目映射失败: {}", e))?;
+
+        for (repo, project) in &repos {
+            if !repo.contains('/') {
+                return Err(anyhow!("仓库名无效: {}，请使用 owner/repo 格式", repo));
+            }
+            if project.trim().is_empty() {
+                return Err(anyhow!("仓库 {} 的 Taiga 项目不能为空", repo));
+            }
+        }
+
+        Ok(Self {
+            repos: repos.into_iter()
+                .map(|(repo, project)| (repo.to_lowercase(), project.trim().to_string()))
+                .collect(),
+        })
+    }
+
+    /// 仓库（owner/repo）对应的 Taiga 项目 slug
+    pub fn project_for(&self, repo: &str) -> Option<&str> {
+        self.repos.get(&repo.to_lowercase()).map(|project| project.as_str())
+    }
+}
@@ -0,0 +1,392 @@
+use std::collections::HashSet;
+use chrono::{Datelike, NaiveDate};
+use anyhow::{Result, anyhow};
+use futures::future::LocalBoxFuture;
+use worker::console_log;
+
+use crate::database::{DatabaseClient, TaigaTaskRecord, TaskDayContext, TaskDaysMode, TaskStatus, TaskStatusSource};
+
+/// Taiga 任务天数的存储，线上使用 D1，本地可以使用内存或 SQLite 实现
+#[cfg_attr(not(test), allow(dead_code))]
+pub trait TaskStore {
+    /// 查询任务记录，不存在时返回 None
+    fn get_task<'b>(&'b self, task_key: &'b str) -> LocalBoxFuture<'b, Result<Option<TaigaTaskRecord>>>;
+
+    /// 新建或更新任务记录
+    fn save_task<'b>(&'b self, record: &'b TaigaTaskRecord) -> LocalBoxFuture<'b, Result<()>>;
+
+    /// 记录任务的活跃日期，已存在的日期忽略
+    fn record_activity<'b>(&'b self, task_key: &'b str, dates: &'b [&'b str]) -> LocalBoxFuture<'b, Result<()>>;
+
+    /// 统计任务的活跃天数
+    fn count_active_days<'b>(&'b self, task_key: &'b str) -> LocalBoxFuture<'b, Result<i32>>;
+
+    /// 成员在日期范围内（包含首尾两天）的请假日期
+    fn leave_days<'b>(&'b self, login: &'b str, start_date: &'b str, end_date: &'b str) -> LocalBoxFuture<'b, Result<HashSet<NaiveDate>>>;
+
+    /// 归档最后出现日期早于 cutoff 的任务，活跃记录保留，返回归档数量
+    fn archive_tasks<'b>(&'b self, cutoff: &'b str) -> LocalBoxFuture<'b, Result<usize>>;
+
+    /// 恢复已归档的任务，任务不在归档中时返回 None
+    fn restore_task<'b>(&'b self, task_key: &'b str) -> LocalBoxFuture<'b, Result<Option<TaigaTaskRecord>>>;
+}
+
+impl<'a> TaskStore for DatabaseClient<'a> {
+    fn get_task<'b>(&'b self, task_key: &'b str) -> LocalBoxFuture<'b, Result<Option<TaigaTaskRecord>>> {
+        Box::pin(self.find_task_record(task_key))
+    }
+
+    fn save_task<'b>(&'b self, record: &'b TaigaTaskRecord) -> LocalBoxFuture<'b, Result<()>> {
+        Box::pin(self.save_task_record(record))
+    }
+
+    fn record_activity<'b>(&'b self, task_key: &'b str, dates: &'b [&'b str]) -> LocalBoxFuture<'b, Result<()>> {
+        Box::pin(self.record_task_activity(task_key, dates))
+    }
+
+    fn count_active_days<'b>(&'b self, task_key: &'b str) -> LocalBoxFuture<'b, Result<i32>> {
+        Box::pin(DatabaseClient::count_active_days(self, task_key))
+    }
+
+    fn leave_days<'b>(&'b self, login: &'b str, start_date: &'b str, end_date: &'b str) -> LocalBoxFuture<'b, Result<HashSet<NaiveDate>>> {
+        Box::pin(self.get_leave_days(login, start_date, end_date))
+    }
+
+    fn archive_tasks<'b>(&'b self, cutoff: &'b str) -> LocalBoxFuture<'b, Result<usize>> {
+        Box::pin(self.archive_tasks_before(cutoff))
+    }
+
+    fn restore_task<'b>(&'b self, task_key: &'b str) -> LocalBoxFuture<'b, Result<Option<TaigaTaskRecord>>> {
+        Box::pin(self.restore_archived_task(task_key))
+    }
+}
+
+/// 记录任务今日出现，按天数计算方式更新并返回累积天数
+/// completed 表示任务关联的 PR 已全部合并：任务记入当天后标记为已完成，否则自动完成的任务重新打开
+/// 线上的 D1 存储在 DatabaseClient::process_task_keys 中批量执行相同的规则，修改时两处保持一致
+#[cfg_attr(not(test), allow(dead_code))]
+pub async fn record_task<S: TaskStore + ?Sized>(store: &S, task_key: &str, completed: bool, context: &TaskDayContext<'_>) -> Result<i32> {
+    let today = context.today.format("%Y-%m-%d").to_string();
+
+    // 已归档的任务重新出现时先恢复，保留原来的首次出现日期和天数
+    let existing = match store.get_task(task_key).await? {
+        Some(record) => Some(record),
+        None => store.restore_task(task_key).await?,
+    };
+
+    match existing {
+        Some(mut existing) => {
+            // 首次和最后出现日期必定是活跃日，一并写入，兼容活跃记录表创建之前的任务
+            store.record_activity(task_key, &[
+                &existing.first_seen_date,
+                &existing.last_seen_date,
+                &today,
+            ]).await?;
+
+            // 自动标记完成的任务又关联了未合并的 PR 时重新打开，手动标记的完成保持不变
+            if existing.status == TaskStatus::Done && !completed && existing.status_source == TaskStatusSource::Auto {
+                existing.status = TaskStatus::Open;
+            }
+
+            // 已完成的任务只更新最后出现日期，天数不再增加
+            if existing.status == TaskStatus::Done {
+                let total_days = existing.total_days;
+                store.save_task(&TaigaTaskRecord {
+                    last_seen_date: today,
+                    ..existing
+                }).await?;
+                return Ok(total_days);
+            }
+
+            // 手动调整的天数加在计算结果上
+            let counted_days = match context.mode {
+                TaskDaysMode::Span => calculate_work_days(store, &existing.first_seen_date, &today, context).await?,
+                TaskDaysMode::Active => store.count_active_days(task_key).await?,
+            };
+            let total_days = (counted_days + existing.extra_days).max(1);
+
+            let mut record = TaigaTaskRecord {
+                last_seen_date: today,
+                total_days,
+                ..existing
+            };
+            // 关联的 PR 全部合并的任务标记为已完成，当天仍计入天数
+            if completed {
+                record.status = TaskStatus::Done;
+                record.status_source = TaskStatusSource::Auto;
+            }
+            store.save_task(&record).await?;
+
+            Ok(total_days)
+        }
+        None => {
+            let mut record = TaigaTaskRecord::new(task_key, &today);
+            if completed {
+                record.status = TaskStatus::Done;
+            }
+            store.save_task(&record).await?;
+
+            // 两种计算方式都记录活跃日，便于随时切换
+            store.record_activity(task_key, &[&today]).await?;
+
+            Ok(1)
+        }
+    }
+}
+
+/// 计算工作天数（按成员的工作日日历排除节假日、计入调休补班，并排除请假日期）
+pub async fn calculate_work_days<S: TaskStore + ?Sized>(store: &S, start_date: &str, end_date: &str, context: &TaskDayContext<'_>) -> Result<i32> {
+    let start = NaiveDate::parse_from_str(start_date, "%Y-%m-%d")
+        .map_err(|e| anyhow!("起始日期格式错误: {}", e))?;
+    let end = NaiveDate::parse_from_str(end_date, "%Y-%m-%d")
+        .map_err(|e| anyhow!("结束日期格式错误: {}", e))?;
+
+    if end < start {
+        return Ok(1);
+    }
+
+    let working_days = match context.calendar.working_days(start, end).await {
+        Ok(days) => days,
+        Err(e) => {
+            console_log!("⚠️ 查询工作日日历失败: {}，按周一到周五上班计算", e);
+            weekdays_between(start, end)
+        }
+    };
+
+    let leave_days = match store.leave_days(context.login, start_date, end_date).await {
+        Ok(days) => days,
+        Err(e) => {
+            console_log!("⚠️ 查询 {} 的请假记录失败: {}，不排除请假日期", context.login, e);
+            HashSet::new()
+        }
+    };
+
+    let work_days = working_days.difference(&leave_days).count() as i32;
+
+    Ok(work_days.max(1)) // 至少返回1天
+}
+
+/// 日期范围内（包含首尾两天）的周一到周五
+fn weekdays_between(start: NaiveDate, end: NaiveDate) -> HashSet<NaiveDate> {
+    let mut days = HashSet::new();
+    let mut current = start;
+    while current <= end {
+        if current.weekday().number_from_monday() <= 5 {
+            days.insert(current);
+        }
+        current = match current.succ_opt() {
+            Some(next) => next,
+            None => break,
+        };
+    }
+    days
+}
+
+/// 所有 TaskStore 实现共用的一致性测试
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod conformance {
+    use super::*;
+    use futures::executor::block_on;
+
+    use crate::calendar::WeekendCalendar;
+    use crate::leave::LeaveRecord;
+    use crate::memory_task_store::MemoryTaskStore;
+    use crate::sqlite_task_store::SqliteTaskStore;
+
+    const TASK: &str = "demo-project#41";
+    const LOGIN: &str = "octocat";
+
+    /// 测试需要写入请假记录，这部分不属于 TaskStore
+    trait TestStore: TaskStore {
+        fn insert_leave(&self, leave: &LeaveRecord) -> Result<()>;
+    }
+
+    impl TestStore for MemoryTaskStore {
+        fn insert_leave(&self, leave: &LeaveRecord) -> Result<()> {
+            self.add_leave(leave)
+        }
+    }
+
+    impl TestStore for SqliteTaskStore {
+        fn insert_leave(&self, leave: &LeaveRecord) -> Result<()> {
+            self.add_leave(leave)
+        }
+    }
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn context(today: &str, mode: TaskDaysMode) -> TaskDayContext<'static> {
+        TaskDayContext {
+            login: LOGIN,
+            calendar: &WeekendCalendar,
+            mode,
+            today: date(today),
+        }
+    }
+
+    fn record(store: &dyn TestStore, today: &str, mode: TaskDaysMode) -> i32 {
+        block_on(record_task(store, TASK, false, &context(today, mode))).unwrap()
+    }
+
+    fn record_completed(store: &dyn TestStore, today: &str, mode: TaskDaysMode) -> i32 {
+        block_on(record_task(store, TASK, true, &context(today, mode))).unwrap()
+    }
+
+    fn new_task_starts_at_one_day(store: &dyn TestStore) {
+        assert_eq!(record(store, "2024-01-15", TaskDaysMode::Active), 1);
+
+        let saved = block_on(store.get_task(TASK)).unwrap().unwrap();
+        assert_eq!(saved.first_seen_date, "2024-01-15");
+        assert_eq!(saved.last_seen_date, "2024-01-15");
+        assert_eq!(saved.total_days, 1);
+        assert_eq!(block_on(store.get_task("demo-project#404")).unwrap(), None);
+    }
+
+    fn active_mode_counts_distinct_days(store: &dyn TestStore) {
+        record(store, "2024-01-15", TaskDaysMode::Active);
+        record(store, "2024-01-17", TaskDaysMode::Active);
+        assert_eq!(record(store, "2024-01-17", TaskDaysMode::Active), 2);
+        assert_eq!(record(store, "2024-01-22", TaskDaysMode::Active), 3);
+
+        let saved = block_on(store.get_task(TASK)).unwrap().unwrap();
+        assert_eq!(saved.first_seen_date, "2024-01-15");
+        assert_eq!(saved.last_seen_date, "2024-01-22");
+        assert_eq!(saved.total_days, 3);
+    }
+
+    fn span_mode_counts_working_days(store: &dyn TestStore) {
+        record(store, "2024-01-15", TaskDaysMode::Span);
+        // 周一到下周一，跳过周末
+        assert_eq!(record(store, "2024-01-22", TaskDaysMode::Span), 6);
+    }
+
+    fn span_mode_excludes_leave(store: &dyn TestStore) {
+        store.insert_leave(&LeaveRecord {
+            id: None,
+            login: LOGIN.to_string(),
+            start_date: "2024-01-16".to_string(),
+            end_date: "2024-01-17".to_string(),
+            reason: None,
+            source: "manual".to_string(),
+        }).unwrap();
+        store.insert_leave(&LeaveRecord {
+            id: None,
+            login: "someone-else".to_string(),
+            start_date: "2024-01-18".to_string(),
+            end_date: "2024-01-18".to_string(),
+            reason: None,
+            source: "manual".to_string(),
+        }).unwrap();
+
+        record(store, "2024-01-15", TaskDaysMode::Span);
+        assert_eq!(record(store, "2024-01-22", TaskDaysMode::Span), 4);
+    }
+
+    fn archive_moves_stale_tasks(store: &dyn TestStore) {
+        record(store, "2024-01-02", TaskDaysMode::Active);
+        let recent = TaigaTaskRecord::new("demo-project#42", "2024-02-01");
+        block_on(store.save_task(&recent)).unwrap();
+
+        assert_eq!(block_on(store.archive_tasks("2024-01-15")).unwrap(), 1);
+
+        assert_eq!(block_on(store.get_task(TASK)).unwrap(), None);
+        assert_eq!(block_on(store.get_task("demo-project#42")).unwrap(), Some(recent));
+        assert_eq!(block_on(store.restore_task("demo-project#404")).unwrap(), None);
+    }
+
+    fn archived_task_resumes_history(store: &dyn TestStore) {
+        record(store, "2024-01-02", TaskDaysMode::Active);
+        record(store, "2024-01-03", TaskDaysMode::Active);
+        block_on(store.archive_tasks("2024-02-01")).unwrap();
+
+        // 重新出现时接着原来的天数累积，而不是从 1 开始
+        assert_eq!(record(store, "2024-03-04", TaskDaysMode::Active), 3);
+
+        let saved = block_on(store.get_task(TASK)).unwrap().unwrap();
+        assert_eq!(saved.first_seen_date, "2024-01-02");
+        assert_eq!(saved.last_seen_date, "2024-03-04");
+        assert_eq!(block_on(store.restore_task(TASK)).unwrap(), None);
+    }
+
+    fn done_task_keeps_its_days(store: &dyn TestStore) {
+        record(store, "2024-01-15", TaskDaysMode::Active);
+        assert_eq!(record_completed(store, "2024-01-16", TaskDaysMode::Active), 2);
+
+        let saved = block_on(store.get_task(TASK)).unwrap().unwrap();
+        assert_eq!(saved.status, TaskStatus::Done);
+        assert_eq!(saved.status_source, TaskStatusSource::Auto);
+
+        assert_eq!(record_completed(store, "2024-01-17", TaskDaysMode::Active), 2);
+
+        // 天数不再增加，但最后出现日期仍然更新
+        let saved = block_on(store.get_task(TASK)).unwrap().unwrap();
+        assert_eq!(saved.status, TaskStatus::Done);
+        assert_eq!(saved.total_days, 2);
+        assert_eq!(saved.last_seen_date, "2024-01-17");
+    }
+
+    fn auto_done_task_reopens(store: &dyn TestStore) {
+        record_completed(store, "2024-01-15", TaskDaysMode::Active);
+        assert_eq!(block_on(store.get_task(TASK)).unwrap().unwrap().status, TaskStatus::Done);
+
+        // 又关联了未合并的 PR，重新打开并接着累积天数
+        assert_eq!(record(store, "2024-01-16", TaskDaysMode::Active), 2);
+
+        let saved = block_on(store.get_task(TASK)).unwrap().unwrap();
+        assert_eq!(saved.status, TaskStatus::Open);
+        assert_eq!(saved.last_seen_date, "2024-01-16");
+    }
+
+    fn manual_done_task_stays_done(store: &dyn TestStore) {
+        record(store, "2024-01-15", TaskDaysMode::Active);
+        record(store, "2024-01-16", TaskDaysMode::Active);
+
+        let mut saved = block_on(store.get_task(TASK)).unwrap().unwrap();
+        saved.status = TaskStatus::Done;
+        saved.status_source = TaskStatusSource::Manual;
+        block_on(store.save_task(&saved)).unwrap();
+
+        assert_eq!(record(store, "2024-01-17", TaskDaysMode::Active), 2);
+
+        let saved = block_on(store.get_task(TASK)).unwrap().unwrap();
+        assert_eq!(saved.status, TaskStatus::Done);
+        assert_eq!(saved.status_source, TaskStatusSource::Manual);
+        assert_eq!(saved.last_seen_date, "2024-01-17");
+    }
+
+    fn extra_days_are_added(store: &dyn TestStore) {
+        record(store, "2024-01-15", TaskDaysMode::Active);
+
+        let mut saved = block_on(store.get_task(TASK)).unwrap().unwrap();
+        saved.extra_days = 3;
+        block_on(store.save_task(&saved)).unwrap();
+
+        assert_eq!(record(store, "2024-01-16", TaskDaysMode::Active), 5);
+        assert_eq!(record(store, "2024-01-22", TaskDaysMode::Span), 9);
+    }
+
+    fn run_suite(new_store: impl Fn() -> Box<dyn TestStore>) {
+        new_task_starts_at_one_day(new_store().as_ref());
+        active_mode_counts_distinct_days(new_store().as_ref());
+        span_mode_counts_working_days(new_store().as_ref());
+        span_mode_excludes_leave(new_store().as_ref());
+        archive_moves_stale_tasks(new_store().as_ref());
+        archived_task_resumes_history(new_store().as_ref());
+        done_task_keeps_its_days(new_store().as_ref());
+        auto_done_task_reopens(new_store().as_ref());
+        manual_done_task_stays_done(new_store().as_ref());
+        extra_days_are_added(new_store().as_ref());
+    }
+
+    #[test]
+    fn memory_store() {
+        run_suite(|| Box::new(MemoryTaskStore::new()));
+    }
+
+    #[test]
+    fn sqlite_store() {
+        run_suite(|| Box::new(SqliteTaskStore::open_in_memory().unwrap()));
+    }
+}
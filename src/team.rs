@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, NaiveTime, Timelike, Utc};
+use chrono_tz::Tz;
+use anyhow::{Result, anyhow};
+
+use crate::calendar::CalendarKind;
+
+/// 未配置团队成员时使用的 GitHub 搜索条件，即 GITHUB_TOKEN 对应的用户
+pub const TOKEN_OWNER: &str = "@me";
+
+fn default_calendar() -> String {
+    "cn".to_string()
+}
+
+fn default_timezone() -> String {
+    "Asia/Shanghai".to_string()
+}
+
+fn default_send_time() -> String {
+    "18:00".to_string()
+}
+
+/// 团队成员配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamMember {
+    pub login: String,                 // GitHub 用户名
+    #[serde(default)]
+    pub display_name: Option<String>,  // 报告中显示的名字
+    #[serde(default = "default_calendar")]
+    pub calendar: String,              // 日历：cn、weekend 或 ics:<名称>
+    #[serde(default = "default_timezone")]
+    pub timezone: String,              // IANA 时区，例如 Asia/Shanghai、America/Los_Angeles
+    #[serde(default = "default_send_time")]
+    pub send_time: String,             // 本地发送时间 HH:MM
+}
+
+impl TeamMember {
+    /// 未配置团队成员时，使用 GITHUB_TOKEN 对应的用户，沿用中国大陆日历和北京时间 18:00 发送
+    pub fn token_owner() -> Self {
+        Self {
+            login: TOKEN_OWNER.to_string(),
+            display_name: None,
+            calendar: default_calendar(),
+            timezone: default_timezone(),
+            send_time: default_send_time(),
+        }
+    }
+
+    /// 是否为 GITHUB_TOKEN 对应的用户
+    pub fn is_token_owner(&self) -> bool {
+        self.login == TOKEN_OWNER
+    }
+
+    /// 报告和日志中显示的名字
+    pub fn name(&self) -> &str {
+        self.display_name.as_deref().filter(|name| !name.is_empty()).unwrap_or(&self.login)
+    }
+
+    /// 解析成员的日历配置
+    pub fn calendar_kind(&self) -> Result<CalendarKind> {
+        CalendarKind::parse(&self.calendar)
+    }
+
+    /// 解析成员的时区
+    pub fn tz(&self) -> Result<Tz> {
+        self.timezone.trim().parse::<Tz>()
+            .map_err(|_| anyhow!("无效的时区: {}，请使用 IANA 时区名称，例如 Asia/Shanghai", self.timezone))
+    }
+
+    /// 解析成员的本地发送时间
+    pub fn local_send_time(&self) -> Result<NaiveTime> {
+        NaiveTime::parse_from_str(self.send_time.trim(), "%H:%M")
+            .map_err(|_| anyhow!("无效的发送时间: {}，请使用 HH:MM 格式", self.send_time))
+    }
+
+    /// 成员所在时区的当前日期
+    pub fn local_date(&self, now: DateTime<Utc>) -> Result<NaiveDate> {
+        Ok(now.with_timezone(&self.tz()?).date_naive())
+    }
+
+    /// 本地时间是否已到达当天的发送时间
+    pub fn is_past_send_time(&self, now: DateTime<Utc>) -> Result<bool> {
+        let local_time = now.with_timezone(&self.tz()?).time();
+        Ok(local_time >= self.local_send_time()?)
+    }
+
+    /// 定时任务每小时触发一次，本地时间落在发送时间之后的一小时内时发送
+    pub fn is_send_time(&self, now: DateTime<Utc>) -> Result<bool> {
+        let local_time = now.with_timezone(&self.tz()?).time();
+        let send_time = self.local_send_time()?;
+
+        let local_minutes = (local_time.hour() * 60 + local_time.minute()) as i32;
+        let send_minutes = (send_time.hour() * 60 + send_time.minute()) as i32;
+        Ok((local_minutes - send_minutes).rem_euclid(24 * 60) < 60)
+    }
+}
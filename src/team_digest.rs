@@ -0,0 +1,179 @@
+use crate::github_api::PrStatus;
+use crate::locale::Locale;
+use crate::report_history::StoredReport;
+
+/// 每日报告的发送方式，通过 REPORT_DELIVERY 环境变量配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Delivery {
+    /// 每个成员单独发送一条消息
+    #[default]
+    Individual,
+    /// 汇总为一条团队日报，发送到团队群
+    Digest,
+}
+
+impl Delivery {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "individual" => Some(Delivery::Individual),
+            "digest" => Some(Delivery::Digest),
+            _ => None,
+        }
+    }
+}
+
+/// 团队日报的消息格式，通过 TEAM_DIGEST_FORMAT 环境变量配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DigestFormat {
+    /// 纯文本消息
+    #[default]
+    Text,
+    /// 飞书消息卡片，每个成员一个分区
+    Card,
+}
+
+impl DigestFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "text" => Some(DigestFormat::Text),
+            "card" => Some(DigestFormat::Card),
+            _ => None,
+        }
+    }
+}
+
+/// 团队日报中一个成员的部分
+#[derive(Debug, Clone)]
+pub struct DigestItem {
+    pub name: String,
+    pub report: String,
+    pub merged: usize,
+    pub open: usize,
+    pub error: Option<String>,
+}
+
+impl DigestItem {
+    /// 根据成员当天的报告统计 PR 数量
+    pub fn from_report(name: &str, stored: &StoredReport) -> Self {
+        let entries = stored.parsed_entries();
+        Self {
+            name: name.to_string(),
+            report: stored.report.trim().to_string(),
+            merged: entries.iter().filter(|entry| entry.status == PrStatus::Merged).count(),
+            open: entries.iter().filter(|entry| entry.status == PrStatus::Open).count(),
+            error: None,
+        }
+    }
+
+    /// 报告生成失败的成员
+    pub fn failed(name: &str, error: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            report: String::new(),
+            merged: 0,
+            open: 0,
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+/// 团队日报：按成员分组的报告和 PR 统计
+pub struct TeamDigest {
+    pub items: Vec<DigestItem>,
+    locale: Locale,
+}
+
+impl TeamDigest {
+    pub fn new(items: Vec<DigestItem>, locale: Locale) -> Self {
+        Self { items, locale }
+    }
+
+    /// 是否至少有一个成员生成成功
+    pub fn has_reports(&self) -> bool {
+        self.items.iter().any(|item| item.error.is_none())
+    }
+
+    /// 全部成员的 PR 统计
+    pub fn totals_line(&self) -> String {
+        let merged: usize = self.items.iter().map(|item| item.merged).sum();
+        let open: usize = self.items.iter().map(|item| item.open).sum();
+        self.counts_line(merged, open)
+    }
+
+    fn counts_line(&self, merged: usize, open: usize) -> String {
+        let texts = self.locale.texts();
+        format!("{}{} | {}{}", texts.merged_count, merged, texts.open_count, open)
+    }
+
+    /// 每个成员一个分区：(标题, 内容)
+    pub fn sections(&self) -> Vec<(String, String)> {
+        let texts = self.locale.texts();
+        self.items.iter()
+            .map(|item| {
+                let heading = format!("【{}】{}", item.name, self.counts_line(item.merged, item.open));
+                let body = match &item.error {
+                    Some(error) => format!("{}{}", texts.digest_failed, error),
+                    None if item.report.is_empty() => texts.no_prs.to_string(),
+                    None => item.report.clone(),
+                };
+                (heading, body)
+            })
+            .collect()
+    }
+
+    /// 渲染为纯文本消息正文
+    pub fn render_text(&self) -> String {
+        let mut text = format!("{}\n", self.totals_line());
+        for (heading, body) in self.sections() {
+            text.push_str(&format!("\n{}\n{}\n", heading, body));
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github_api::StandupEntry;
+
+    fn entry(status: PrStatus) -> StandupEntry {
+        StandupEntry {
+            title: "修复登录".to_string(),
+            repo: "acme/web".to_string(),
+            status,
+            taiga_id: None,
+            task_key: None,
+            days: None,
+            task_done: false,
+            project_code: "web".to_string(),
+            work_summary: String::new(),
+            html_url: "https://github.com/acme/web/pull/1".to_string(),
+        }
+    }
+
+    #[test]
+    fn render_text_lists_totals_and_each_member() {
+        let alice = StoredReport::new("alice", "2025-10-17", "[2]web#12-修复登录\n", "", &[entry(PrStatus::Merged), entry(PrStatus::Open)]);
+        let bob = StoredReport::new("bob", "2025-10-17", "", "", &[entry(PrStatus::Merged), entry(PrStatus::Closed)]);
+        let digest = TeamDigest::new(vec![
+            DigestItem::from_report("Alice", &alice),
+            DigestItem::from_report("Bob", &bob),
+            DigestItem::failed("Carol", "GitHub 超时"),
+        ], Locale::default());
+
+        assert_eq!(digest.render_text(), concat!(
+            "已合并 PR：2 | 进行中 PR：1\n",
+            "\n【Alice】已合并 PR：1 | 进行中 PR：1\n[2]web#12-修复登录\n",
+            "\n【Bob】已合并 PR：1 | 进行中 PR：0\n今天没有创建任何 PR，可能没有代码提交工作完成。\n",
+            "\n【Carol】已合并 PR：0 | 进行中 PR：0\n报告生成失败：GitHub 超时\n",
+        ));
+        assert!(digest.has_reports());
+    }
+
+    #[test]
+    fn digest_without_successful_reports_has_no_reports() {
+        let digest = TeamDigest::new(vec![DigestItem::failed("Carol", "GitHub 超时")], Locale::En);
+        assert!(!digest.has_reports());
+        assert_eq!(digest.render_text(), "Merged PRs: 0 | Open PRs: 0\n\n【Carol】Merged PRs: 0 | Open PRs: 0\nReport failed: GitHub 超时\n");
+    }
+}